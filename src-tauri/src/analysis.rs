@@ -0,0 +1,1381 @@
+// src-tauri/src/analysis.rs
+use crate::cache::WaveformCache;
+use crate::error::PlayerError;
+use crate::media_player::{probe_hint, read_track_metadata};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::ops::ControlFlow;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::{Error as SymphoniaError, SeekErrorKind};
+use symphonia::core::formats::{SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::units::Time;
+use tauri::AppHandle;
+
+// Basic properties of a decoded stream
+pub(crate) struct DecodedStream {
+    pub sample_rate: u32,
+    pub channels: usize,
+    pub frames_decoded: u64,
+}
+
+// Whether Symphonia can open the file and has a decoder for its audio track
+pub(crate) fn probes_successfully(path: &Path) -> bool {
+    let Ok(source) = File::open(path) else {
+        return false;
+    };
+    let mss = MediaSourceStream::new(Box::new(source), Default::default());
+    let hint = probe_hint(path);
+
+    let Ok(probed) =
+        symphonia::default::get_probe().format(&hint, mss, &Default::default(), &Default::default())
+    else {
+        return false;
+    };
+
+    probed
+        .format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .is_some_and(|track| {
+            symphonia::default::get_codecs()
+                .make(&track.codec_params, &DecoderOptions::default())
+                .is_ok()
+        })
+}
+
+// Decode a file with Symphonia, handing each packet's samples to `on_buffer` as
+// interleaved f32 along with the channel count and sample rate. Decoding stops early
+// if `on_buffer` returns `ControlFlow::Break`.
+pub(crate) fn decode_interleaved<F>(path: &Path, on_buffer: F) -> Result<DecodedStream, PlayerError>
+where
+    F: FnMut(&[f32], usize, u32) -> ControlFlow<()>,
+{
+    decode_interleaved_from(path, 0.0, on_buffer)
+}
+
+// Like decode_interleaved, but seeks to `start_secs` first. Seeking lands on a packet
+// boundary, so samples before the requested time are dropped before `on_buffer` sees
+// them. Starting past the end decodes nothing.
+pub(crate) fn decode_interleaved_from<F>(
+    path: &Path,
+    start_secs: f64,
+    mut on_buffer: F,
+) -> Result<DecodedStream, PlayerError>
+where
+    F: FnMut(&[f32], usize, u32) -> ControlFlow<()>,
+{
+    let source = File::open(path)?;
+    let mss = MediaSourceStream::new(Box::new(source), Default::default());
+    let hint = probe_hint(path);
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &Default::default(), &Default::default())
+        .map_err(|e| PlayerError::UnsupportedFormat(format!("Failed to probe: {}", e)))?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| PlayerError::UnsupportedFormat("No audio track found".to_string()))?;
+    let track_id = track.id;
+    let time_base = track.codec_params.time_base;
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(0);
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| PlayerError::UnsupportedFormat(format!("No decoder available: {}", e)))?;
+
+    let mut sample_buf: Option<SampleBuffer<f32>> = None;
+    let mut frames_decoded = 0u64;
+
+    // Timestamp the caller asked for; packets before it are trimmed
+    let mut skip_until_ts = 0u64;
+    if start_secs > 0.0 {
+        let seek_to = SeekTo::Time {
+            time: Time::from(start_secs),
+            track_id: Some(track_id),
+        };
+        match format.seek(SeekMode::Accurate, seek_to) {
+            Ok(seeked) => skip_until_ts = seeked.required_ts,
+            Err(SymphoniaError::SeekError(SeekErrorKind::OutOfRange)) => {
+                return Ok(DecodedStream {
+                    sample_rate,
+                    channels,
+                    frames_decoded,
+                })
+            }
+            Err(e) => return Err(PlayerError::Decode(format!("Failed to seek: {}", e))),
+        }
+        decoder.reset();
+    }
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => {
+                return Err(PlayerError::Decode(format!("Failed to read packet: {}", e)))
+            }
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let packet_ts = packet.ts();
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // Corrupt frames are skipped rather than aborting the whole file
+            Err(SymphoniaError::DecodeError(e)) => {
+                eprintln!("⚠️ Skipping undecodable packet: {}", e);
+                continue;
+            }
+            Err(e) => return Err(PlayerError::Decode(e.to_string())),
+        };
+
+        let spec = *decoded.spec();
+        sample_rate = spec.rate;
+        channels = spec.channels.count();
+
+        let needs_new_buf = sample_buf
+            .as_ref()
+            .is_none_or(|b| b.capacity() < decoded.capacity() * channels);
+        if needs_new_buf {
+            sample_buf = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+        }
+
+        if let Some(buf) = sample_buf.as_mut() {
+            buf.copy_interleaved_ref(decoded);
+            let mut samples = buf.samples();
+            if packet_ts < skip_until_ts {
+                let behind = skip_until_ts - packet_ts;
+                let skip_frames = match time_base {
+                    Some(tb) => {
+                        let time = tb.calc_time(behind);
+                        ((time.seconds as f64 + time.frac) * sample_rate as f64).round() as usize
+                    }
+                    None => behind as usize,
+                };
+                samples = &samples[(skip_frames * channels.max(1)).min(samples.len())..];
+            }
+            if samples.is_empty() {
+                continue;
+            }
+
+            frames_decoded += (samples.len() / channels.max(1)) as u64;
+            if on_buffer(samples, channels.max(1), sample_rate).is_break() {
+                break;
+            }
+        }
+    }
+
+    Ok(DecodedStream {
+        sample_rate,
+        channels,
+        frames_decoded,
+    })
+}
+
+#[tauri::command]
+pub async fn generate_waveform(
+    app: AppHandle,
+    file_path: String,
+    num_buckets: usize,
+) -> Result<Vec<f32>, PlayerError> {
+    // A missing cache only costs us the recompute, never the waveform itself
+    let cache = match WaveformCache::new(&app) {
+        Ok(cache) => Some(cache),
+        Err(e) => {
+            eprintln!("⚠️ Waveform cache unavailable: {}", e);
+            None
+        }
+    };
+
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+        if num_buckets == 0 {
+            return Err(PlayerError::InvalidInput(
+                "num_buckets must be greater than zero".to_string(),
+            ));
+        }
+
+        if let Some(peaks) = cache.as_ref().and_then(|c| c.get(&file_path, num_buckets)) {
+            return Ok(peaks);
+        }
+
+        // We don't always know the length up front, so collect peaks over small fixed
+        // windows first and fold them into the requested number of buckets at the end
+        const WINDOW_FRAMES: usize = 256;
+        let mut window_peaks: Vec<f32> = Vec::new();
+        let mut window_peak = 0.0f32;
+        let mut window_len = 0usize;
+
+        decode_interleaved(path, |samples, channels, _| {
+            for frame in samples.chunks(channels) {
+                // Average the channels so stereo content isn't double counted
+                let mono = frame.iter().sum::<f32>() / channels as f32;
+                window_peak = window_peak.max(mono.abs());
+                window_len += 1;
+                if window_len == WINDOW_FRAMES {
+                    window_peaks.push(window_peak);
+                    window_peak = 0.0;
+                    window_len = 0;
+                }
+            }
+            ControlFlow::Continue(())
+        })?;
+        if window_len > 0 {
+            window_peaks.push(window_peak);
+        }
+
+        let mut peaks = vec![0.0f32; num_buckets];
+        if !window_peaks.is_empty() {
+            for (bucket, peak) in peaks.iter_mut().enumerate() {
+                let start = bucket * window_peaks.len() / num_buckets;
+                let end = ((bucket + 1) * window_peaks.len() / num_buckets).max(start + 1);
+                *peak = window_peaks[start..end.min(window_peaks.len())]
+                    .iter()
+                    .fold(0.0f32, |a, &b| a.max(b));
+            }
+        }
+
+        // Normalize so the loudest bucket is 1.0
+        let max_peak = peaks.iter().fold(0.0f32, |a, &b| a.max(b));
+        if max_peak > 0.0 {
+            for peak in peaks.iter_mut() {
+                *peak /= max_peak;
+            }
+        }
+
+        if let Some(cache) = &cache {
+            cache.insert(&file_path, num_buckets, &peaks);
+        }
+
+        Ok(peaks)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Size of the header decode_preview puts in front of the samples: the sample rate and
+// channel count, each a little-endian u32
+const PREVIEW_HEADER_LEN: usize = 8;
+
+// Decode `duration_secs` of audio starting at `start_secs`. The window is clamped to the
+// file, so a preview running past the end is just shorter (or empty).
+//
+// Returned as raw bytes rather than JSON, since a number array is many times the size of
+// the audio: the header, then interleaved signed 16-bit little-endian samples.
+#[tauri::command]
+pub async fn decode_preview(
+    file_path: String,
+    start_secs: f64,
+    duration_secs: f64,
+) -> Result<tauri::ipc::Response, PlayerError> {
+    if !start_secs.is_finite() || start_secs < 0.0 {
+        return Err(PlayerError::InvalidInput(format!(
+            "Preview start must be a non-negative number of seconds, got {}",
+            start_secs
+        )));
+    }
+    if !duration_secs.is_finite() || duration_secs <= 0.0 || duration_secs > 60.0 {
+        return Err(PlayerError::InvalidInput(format!(
+            "Preview duration must be between 0 and 60 seconds, got {}",
+            duration_secs
+        )));
+    }
+
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let mut bytes = vec![0; PREVIEW_HEADER_LEN];
+        let mut frames_left: Option<u64> = None;
+
+        let stream = decode_interleaved_from(path, start_secs, |samples, channels, sample_rate| {
+            let remaining = frames_left
+                .get_or_insert_with(|| (duration_secs * sample_rate as f64).round() as u64);
+            let take = (samples.len() / channels).min(*remaining as usize);
+            for &sample in &samples[..take * channels] {
+                let quantized = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+                bytes.extend_from_slice(&quantized.to_le_bytes());
+            }
+            *remaining -= take as u64;
+            if *remaining == 0 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })?;
+
+        bytes[..4].copy_from_slice(&stream.sample_rate.to_le_bytes());
+        bytes[4..PREVIEW_HEADER_LEN].copy_from_slice(&(stream.channels as u32).to_le_bytes());
+        Ok(tauri::ipc::Response::new(bytes))
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Hash of the decoded audio, so files that differ only in tags or container hash the same
+pub(crate) fn audio_fingerprint(path: &Path) -> Result<String, PlayerError> {
+    // Only the first 30 seconds are hashed; that's plenty to tell songs apart
+    const HASH_SECONDS: u64 = 30;
+    let mut hasher = Sha256::new();
+    let mut frames_hashed = 0u64;
+
+    decode_interleaved(path, |samples, channels, sample_rate| {
+        let frame_limit = HASH_SECONDS * sample_rate as u64;
+        for frame in samples.chunks(channels) {
+            if frames_hashed >= frame_limit {
+                return ControlFlow::Break(());
+            }
+            // Downmix to mono and quantize to 16 bits so tiny float differences
+            // between decoders don't change the hash
+            let mono = frame.iter().sum::<f32>() / channels as f32;
+            let quantized = (mono.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+            hasher.update(quantized.to_le_bytes());
+            frames_hashed += 1;
+        }
+        ControlFlow::Continue(())
+    })?;
+
+    if frames_hashed == 0 {
+        return Err(PlayerError::Decode("No audio could be decoded".to_string()));
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[tauri::command]
+pub async fn compute_audio_hash(file_path: String) -> Result<String, PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        audio_fingerprint(path)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Peak level report for a whole file
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct PeakInfo {
+    // Highest inter-sample peak across all channels, measured with the oversampling
+    // true-peak meter from ITU-R BS.1770 (4x below 96kHz) that EBU R128 refers to
+    pub true_peak_dbfs: f32,
+    // Highest sample peak of each channel
+    pub channel_peaks_dbfs: Vec<f32>,
+    // Samples at or beyond full scale
+    pub clipped_samples: u64,
+}
+
+// dBFS of a linear peak, floored so digital silence doesn't serialize as -inf (null)
+fn to_dbfs(peak: f32) -> f32 {
+    const SILENCE_DBFS: f32 = -120.0;
+    if peak > 0.0 {
+        (20.0 * peak.log10()).max(SILENCE_DBFS)
+    } else {
+        SILENCE_DBFS
+    }
+}
+
+#[tauri::command]
+pub async fn analyze_peak(file_path: String) -> Result<PeakInfo, PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let mut channel_peaks: Vec<f32> = Vec::new();
+        let mut clipped_samples = 0u64;
+        let mut meter: Option<ebur128::EbuR128> = None;
+        let mut meter_error: Option<ebur128::Error> = None;
+
+        decode_interleaved(path, |samples, channels, sample_rate| {
+            if channel_peaks.len() < channels {
+                channel_peaks.resize(channels, 0.0);
+            }
+            for frame in samples.chunks(channels) {
+                for (peak, &sample) in channel_peaks.iter_mut().zip(frame) {
+                    let level = sample.abs();
+                    *peak = peak.max(level);
+                    if level >= 1.0 {
+                        clipped_samples += 1;
+                    }
+                }
+            }
+
+            // Peaks between samples only show up after oversampling
+            if meter.is_none() {
+                match ebur128::EbuR128::new(channels as u32, sample_rate, ebur128::Mode::TRUE_PEAK)
+                {
+                    Ok(m) => meter = Some(m),
+                    Err(e) => {
+                        meter_error = Some(e);
+                        return ControlFlow::Break(());
+                    }
+                }
+            }
+            if let Some(m) = meter.as_mut() {
+                if let Err(e) = m.add_frames_f32(samples) {
+                    meter_error = Some(e);
+                    return ControlFlow::Break(());
+                }
+            }
+            ControlFlow::Continue(())
+        })?;
+
+        if let Some(e) = meter_error {
+            return Err(PlayerError::Decode(format!("Peak analysis failed: {}", e)));
+        }
+        let sample_peak = channel_peaks.iter().fold(0.0f32, |a, &b| a.max(b));
+        let true_peak = meter.as_ref().map_or(0.0, |m| {
+            (0..m.channels())
+                .filter_map(|channel| m.true_peak(channel).ok())
+                .fold(0.0f64, f64::max) as f32
+        });
+
+        Ok(PeakInfo {
+            // The reconstructed waveform can't peak below its own samples
+            true_peak_dbfs: to_dbfs(true_peak.max(sample_peak)),
+            channel_peaks_dbfs: channel_peaks.into_iter().map(to_dbfs).collect(),
+            clipped_samples,
+        })
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Run a whole file through an EBU R128 meter in the given mode
+fn loudness_meter(path: &Path, mode: ebur128::Mode) -> Result<ebur128::EbuR128, PlayerError> {
+    // The meter needs the channel count and rate, which we only learn from the first
+    // decoded packet
+    let mut meter: Option<ebur128::EbuR128> = None;
+    let mut meter_error: Option<ebur128::Error> = None;
+
+    decode_interleaved(path, |samples, channels, sample_rate| {
+        if meter.is_none() {
+            let created =
+                ebur128::EbuR128::new(channels as u32, sample_rate, mode).and_then(|mut m| {
+                    // Mono is played through both speakers, so weight it like stereo
+                    if channels == 1 {
+                        m.set_channel(0, ebur128::Channel::DualMono)?;
+                    }
+                    Ok(m)
+                });
+            match created {
+                Ok(m) => meter = Some(m),
+                Err(e) => {
+                    meter_error = Some(e);
+                    return ControlFlow::Break(());
+                }
+            }
+        }
+
+        if let Some(m) = meter.as_mut() {
+            if let Err(e) = m.add_frames_f32(samples) {
+                meter_error = Some(e);
+                return ControlFlow::Break(());
+            }
+        }
+        ControlFlow::Continue(())
+    })?;
+
+    if let Some(e) = meter_error {
+        return Err(PlayerError::Decode(format!("Loudness analysis failed: {}", e)));
+    }
+    meter.ok_or_else(|| PlayerError::Decode("No audio could be decoded".to_string()))
+}
+
+// Audio that never rises above the absolute gate has no measurable loudness;
+// report the gate itself rather than -inf
+fn gated_loudness(loudness: f64) -> f32 {
+    const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+    if loudness.is_finite() {
+        loudness.max(ABSOLUTE_GATE_LUFS) as f32
+    } else {
+        ABSOLUTE_GATE_LUFS as f32
+    }
+}
+
+#[tauri::command]
+pub async fn measure_loudness(file_path: String) -> Result<f32, PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let meter = loudness_meter(path, ebur128::Mode::I)?;
+        let loudness = meter
+            .loudness_global()
+            .map_err(|e| PlayerError::Decode(format!("Loudness analysis failed: {}", e)))?;
+
+        Ok(gated_loudness(loudness))
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// ReplayGain 2.0 targets -18 LUFS
+const REPLAYGAIN_REFERENCE_LUFS: f32 = -18.0;
+
+// ReplayGain values for one track of an album
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct TrackGain {
+    pub path: String,
+    pub gain_db: f32,
+    // Linear sample peak, 1.0 = full scale
+    pub peak: f32,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct AlbumGain {
+    pub tracks: Vec<TrackGain>,
+    pub album_gain_db: f32,
+    pub album_peak: f32,
+}
+
+// Highest sample peak across all channels of a meter
+fn meter_peak(meter: &ebur128::EbuR128) -> f32 {
+    (0..meter.channels())
+        .filter_map(|channel| meter.sample_peak(channel).ok())
+        .fold(0.0f64, f64::max) as f32
+}
+
+// Album gain has to be measured over the whole album's gated blocks, not averaged from
+// the track values, so each file gets its own meter and the meters are combined at the end
+#[tauri::command]
+pub async fn compute_album_replaygain(file_paths: Vec<String>) -> Result<AlbumGain, PlayerError> {
+    use std::sync::Arc;
+    use tokio::sync::Semaphore;
+
+    if file_paths.is_empty() {
+        return Err(PlayerError::InvalidInput("No tracks given".to_string()));
+    }
+    if let Some(missing) = file_paths.iter().find(|p| !Path::new(p).exists()) {
+        return Err(PlayerError::FileNotFound(missing.clone()));
+    }
+
+    let max_concurrency = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let semaphore = Arc::new(Semaphore::new(max_concurrency));
+    let tasks: Vec<_> = file_paths
+        .into_iter()
+        .map(|file_path| {
+            let sem: Arc<Semaphore> = Arc::clone(&semaphore);
+            tauri::async_runtime::spawn(async move {
+                let _permit = sem
+                    .acquire()
+                    .await
+                    .map_err(|e| PlayerError::Task(e.to_string()))?;
+                // Decode on the blocking pool so it doesn't stall the async workers
+                let decode_path = file_path.clone();
+                let meter = tauri::async_runtime::spawn_blocking(move || {
+                    let mode = ebur128::Mode::I | ebur128::Mode::SAMPLE_PEAK;
+                    loudness_meter(Path::new(&decode_path), mode)
+                })
+                .await
+                .map_err(|e| PlayerError::Task(e.to_string()))?;
+                Ok::<_, PlayerError>((file_path, meter))
+            })
+        })
+        .collect();
+
+    // Await in input order so the per-track results line up with the request
+    let mut meters = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        let (file_path, meter) = task.await.map_err(|e| PlayerError::Task(e.to_string()))??;
+        let meter = meter.map_err(|e| PlayerError::Decode(format!("{}: {}", file_path, e)))?;
+        meters.push((file_path, meter));
+    }
+
+    let mut tracks = Vec::with_capacity(meters.len());
+    for (path, meter) in &meters {
+        let loudness = meter
+            .loudness_global()
+            .map_err(|e| PlayerError::Decode(format!("Loudness analysis failed: {}", e)))?;
+        tracks.push(TrackGain {
+            path: path.clone(),
+            gain_db: REPLAYGAIN_REFERENCE_LUFS - gated_loudness(loudness),
+            peak: meter_peak(meter),
+        });
+    }
+
+    let album_loudness = ebur128::EbuR128::loudness_global_multiple(meters.iter().map(|(_, m)| m))
+        .map_err(|e| PlayerError::Decode(format!("Loudness analysis failed: {}", e)))?;
+    let album = AlbumGain {
+        album_gain_db: REPLAYGAIN_REFERENCE_LUFS - gated_loudness(album_loudness),
+        album_peak: tracks.iter().map(|t| t.peak).fold(0.0f32, f32::max),
+        tracks,
+    };
+
+    println!(
+        "Album ReplayGain: {:.2} dB over {} tracks",
+        album.album_gain_db,
+        album.tracks.len()
+    );
+    Ok(album)
+}
+
+// Where audio first and last rises above `threshold_dbfs`, in seconds, so the player can
+// skip leading and trailing silence between tracks. A file that never crosses the
+// threshold returns (0, duration).
+#[tauri::command]
+pub async fn detect_silence_boundaries(
+    file_path: String,
+    threshold_dbfs: f32,
+) -> Result<(f64, f64), PlayerError> {
+    if !threshold_dbfs.is_finite() || threshold_dbfs > 0.0 {
+        return Err(PlayerError::InvalidInput(format!(
+            "Silence threshold must be at most 0 dBFS, got {}",
+            threshold_dbfs
+        )));
+    }
+
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let threshold = 10f32.powf(threshold_dbfs / 20.0);
+        let mut frame = 0u64;
+        let mut first_loud: Option<u64> = None;
+        let mut last_loud = 0u64;
+
+        let stream = decode_interleaved(path, |samples, channels, _| {
+            for frame_samples in samples.chunks(channels) {
+                if frame_samples.iter().any(|s| s.abs() > threshold) {
+                    first_loud.get_or_insert(frame);
+                    // End of the last loud frame, not its start
+                    last_loud = frame + 1;
+                }
+                frame += 1;
+            }
+            ControlFlow::Continue(())
+        })?;
+
+        if stream.sample_rate == 0 {
+            return Err(PlayerError::Decode("No audio could be decoded".to_string()));
+        }
+        let sample_rate = stream.sample_rate as f64;
+        let duration = stream.frames_decoded as f64 / sample_rate;
+
+        Ok(match first_loud {
+            Some(first) => (first as f64 / sample_rate, last_loud as f64 / sample_rate),
+            None => (0.0, duration),
+        })
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Level of 100ms blocks as mean square power (averaged over channels). With
+// `max_seconds`, decoding stops once that much audio has been read.
+fn block_powers(path: &Path, max_seconds: Option<f64>) -> Result<Vec<f64>, PlayerError> {
+    const BLOCK_SECONDS: f64 = 0.1;
+
+    let mut blocks = Vec::new();
+    let mut sum = 0.0f64;
+    let mut count = 0usize;
+    let mut frames = 0u64;
+
+    decode_interleaved(path, |samples, channels, sample_rate| {
+        let block_frames = ((sample_rate as f64 * BLOCK_SECONDS) as usize).max(1);
+        for frame in samples.chunks(channels) {
+            sum += frame.iter().map(|&s| (s as f64) * (s as f64)).sum::<f64>() / channels as f64;
+            count += 1;
+            if count == block_frames {
+                blocks.push(sum / count as f64);
+                sum = 0.0;
+                count = 0;
+            }
+            frames += 1;
+        }
+        match max_seconds {
+            Some(max) if frames as f64 >= max * sample_rate as f64 => ControlFlow::Break(()),
+            _ => ControlFlow::Continue(()),
+        }
+    })?;
+
+    if count > 0 {
+        blocks.push(sum / count as f64);
+    }
+    Ok(blocks)
+}
+
+// RMS level of a run of blocks in dBFS
+fn rms_dbfs(blocks: &[f64]) -> f32 {
+    if blocks.is_empty() {
+        return to_dbfs(0.0);
+    }
+    let mean = blocks.iter().sum::<f64>() / blocks.len() as f64;
+    to_dbfs(mean.sqrt() as f32)
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct CrossfadeInfo {
+    pub duration: f64,
+    // Seconds between consecutive gain values
+    pub step: f64,
+    pub tail_level_dbfs: f32,
+    pub head_level_dbfs: f32,
+    // Linear gains for the outgoing and incoming track over the crossfade window
+    pub gain_a: Vec<f32>,
+    pub gain_b: Vec<f32>,
+}
+
+// Gain envelopes for crossfading from the end of track A into the start of track B.
+// Both use an equal-power curve; on top of that each track is nudged so that during the
+// fade both play at a level that moves smoothly from A's tail to B's head. The correction
+// is zero where each track is at full volume, so nothing jumps at either end.
+#[tauri::command]
+pub async fn compute_crossfade(
+    track_a_path: String,
+    track_b_path: String,
+    duration_secs: f64,
+) -> Result<CrossfadeInfo, PlayerError> {
+    if !duration_secs.is_finite() || duration_secs <= 0.0 || duration_secs > 30.0 {
+        return Err(PlayerError::InvalidInput(format!(
+            "Crossfade duration must be between 0 and 30 seconds, got {}",
+            duration_secs
+        )));
+    }
+
+    let result = tauri::async_runtime::spawn(async move {
+        for path in [&track_a_path, &track_b_path] {
+            if !Path::new(path).exists() {
+                return Err(PlayerError::FileNotFound(path.clone()));
+            }
+        }
+
+        // The tail of A needs the whole file decoded; B only needs its head
+        let window_blocks = (duration_secs * 10.0).ceil() as usize;
+        let blocks_a = block_powers(Path::new(&track_a_path), None)?;
+        let blocks_b = block_powers(Path::new(&track_b_path), Some(duration_secs))?;
+        let tail_level = rms_dbfs(&blocks_a[blocks_a.len().saturating_sub(window_blocks)..]);
+        let head_level = rms_dbfs(&blocks_b[..blocks_b.len().min(window_blocks)]);
+
+        // Don't chase silence (fade-outs to nothing, silent intros), and cap the correction
+        const SILENCE_DBFS: f32 = -60.0;
+        const MAX_CORRECTION_DB: f32 = 12.0;
+        let difference = if tail_level > SILENCE_DBFS && head_level > SILENCE_DBFS {
+            (head_level - tail_level).clamp(-MAX_CORRECTION_DB, MAX_CORRECTION_DB)
+        } else {
+            0.0
+        };
+
+        // 20 gain values per second, including both ends
+        const STEPS_PER_SECOND: f64 = 20.0;
+        let points = ((duration_secs * STEPS_PER_SECOND).ceil() as usize).max(1) + 1;
+        let db_to_gain = |db: f32| 10f32.powf(db / 20.0);
+
+        let mut gain_a = Vec::with_capacity(points);
+        let mut gain_b = Vec::with_capacity(points);
+        for i in 0..points {
+            let t = i as f32 / (points - 1) as f32;
+            let angle = t * std::f32::consts::FRAC_PI_2;
+            // A moves toward B's level as it fades out; B starts at A's level and settles
+            gain_a.push(angle.cos() * db_to_gain(difference * t));
+            gain_b.push(angle.sin() * db_to_gain(-difference * (1.0 - t)));
+        }
+
+        Ok(CrossfadeInfo {
+            duration: duration_secs,
+            step: duration_secs / (points - 1) as f64,
+            tail_level_dbfs: tail_level,
+            head_level_dbfs: head_level,
+            gain_a,
+            gain_b,
+        })
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Map 0.0-1.0 onto a black → purple → red → yellow → white heat ramp
+fn heat_color(level: f32) -> [u8; 3] {
+    const STOPS: [[f32; 3]; 5] = [
+        [0.0, 0.0, 0.0],
+        [90.0, 20.0, 140.0],
+        [220.0, 40.0, 50.0],
+        [250.0, 200.0, 40.0],
+        [255.0, 255.0, 255.0],
+    ];
+    let scaled = level.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let index = (scaled as usize).min(STOPS.len() - 2);
+    let t = scaled - index as f32;
+    let (a, b) = (STOPS[index], STOPS[index + 1]);
+    [0, 1, 2].map(|c| (a[c] + (b[c] - a[c]) * t).round() as u8)
+}
+
+// Render a spectrogram as a PNG data URL: time left to right, frequency (linear, up to
+// Nyquist) bottom to top. A lossy transcode shows up as a hard shelf where the encoder's
+// lowpass cut everything above ~16 kHz.
+#[tauri::command]
+pub async fn generate_spectrogram(
+    file_path: String,
+    width: u32,
+    height: u32,
+) -> Result<String, PlayerError> {
+    use base64::{engine::general_purpose, Engine as _};
+    use rustfft::{num_complex::Complex, FftPlanner};
+
+    if !(1..=4096).contains(&width) || !(1..=4096).contains(&height) {
+        return Err(PlayerError::InvalidInput(format!(
+            "Spectrogram size must be between 1 and 4096 pixels, got {}x{}",
+            width, height
+        )));
+    }
+
+    // FFTs over the whole track are CPU-bound, so run on the blocking pool
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        const FFT_SIZE: usize = 2048;
+        const FLOOR_DB: f32 = -120.0;
+        let fft = FftPlanner::<f32>::new().plan_fft_forward(FFT_SIZE);
+        // Hann window, so each window's edges don't smear energy across every bin
+        let window: Vec<f32> = (0..FFT_SIZE)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FFT_SIZE as f32).cos())
+            .collect();
+        let bins = FFT_SIZE / 2;
+        // Full-scale sine through the Hann window peaks at a quarter of the FFT size
+        let reference = FFT_SIZE as f32 / 4.0;
+
+        let mut image = image::RgbImage::new(width, height);
+        let mut buffer = vec![Complex::new(0.0f32, 0.0); FFT_SIZE];
+        // FFT one column's samples, zero-padded past the end of the track, into its pixels
+        let mut draw_column = |x: u32, samples: &[f32]| {
+            for (i, slot) in buffer.iter_mut().enumerate() {
+                let sample = samples.get(i).copied().unwrap_or(0.0);
+                *slot = Complex::new(sample * window[i], 0.0);
+            }
+            fft.process(&mut buffer);
+
+            for y in 0..height {
+                // Each pixel row takes the loudest bin in its slice of the spectrum
+                let row = (height - 1 - y) as usize;
+                let low = row * bins / height as usize;
+                let high = ((row + 1) * bins / height as usize).max(low + 1).min(bins);
+                let magnitude = buffer[low..high]
+                    .iter()
+                    .map(|c| c.norm())
+                    .fold(0.0f32, f32::max);
+
+                let db = 20.0 * (magnitude / reference).max(1e-9).log10();
+                let level = (db.max(FLOOR_DB) - FLOOR_DB) / -FLOOR_DB;
+                image.put_pixel(x, y, image::Rgb(heat_color(level)));
+            }
+        };
+
+        // Columns are spread evenly over the track, so its length is needed before
+        // decoding. A file that doesn't report one is decoded once just to count frames.
+        let duration = read_track_metadata(file_path.clone(), false, true).duration;
+        let counted_frames = if duration > 0.0 {
+            None
+        } else {
+            Some(decode_interleaved(path, |_, _, _| ControlFlow::Continue(()))?.frames_decoded)
+        };
+        // Windows overlap whenever there are more columns than FFT_SIZE-sample hops
+        let column_start = |last_start: usize, x: u32| {
+            if width > 1 {
+                last_start * x as usize / (width - 1) as usize
+            } else {
+                0
+            }
+        };
+        let mut last_start: Option<usize> = None;
+
+        // Only the samples from the next column's start on are kept, at most one window's
+        // worth plus a packet
+        let mut kept: std::collections::VecDeque<f32> = std::collections::VecDeque::new();
+        let mut kept_from = 0usize;
+        let mut position = 0usize;
+        let mut next_column = 0u32;
+        let decoded = decode_interleaved(path, |samples, channels, sample_rate| {
+            let last_start = *last_start.get_or_insert_with(|| {
+                let frames = counted_frames.unwrap_or((duration * sample_rate as f64) as u64);
+                (frames as usize).saturating_sub(FFT_SIZE)
+            });
+            let start = |x| column_start(last_start, x);
+
+            for frame in samples.chunks(channels) {
+                if position >= start(next_column) {
+                    if kept.is_empty() {
+                        kept_from = position;
+                    }
+                    kept.push_back(frame.iter().sum::<f32>() / channels as f32);
+                }
+                position += 1;
+
+                while next_column < width && position >= start(next_column) + FFT_SIZE {
+                    let offset = start(next_column) - kept_from;
+                    draw_column(next_column, &kept.make_contiguous()[offset..]);
+                    next_column += 1;
+                    if next_column == width {
+                        return ControlFlow::Break(());
+                    }
+                    let done = (start(next_column) - kept_from).min(kept.len());
+                    kept.drain(..done);
+                    kept_from += done;
+                }
+            }
+            ControlFlow::Continue(())
+        })?;
+        if decoded.frames_decoded == 0 {
+            return Err(PlayerError::Decode("No audio could be decoded".to_string()));
+        }
+
+        // The track ran out before the last columns' windows filled, when its reported
+        // length was too long or it's shorter than one window
+        let last_start = last_start.unwrap_or(0);
+        while next_column < width {
+            let offset = column_start(last_start, next_column)
+                .saturating_sub(kept_from)
+                .min(kept.len());
+            draw_column(next_column, &kept.make_contiguous()[offset..]);
+            next_column += 1;
+        }
+
+        let mut png = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, image::ImageFormat::Png)
+            .map_err(|e| PlayerError::Io(format!("Failed to encode spectrogram: {}", e)))?;
+
+        Ok(format!(
+            "data:image/png;base64,{}",
+            general_purpose::STANDARD.encode(png.into_inner())
+        ))
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Exact duration from decoding every packet, for files whose header is wrong (concatenated
+// MP3s, broken Xing/VBRI frames, truncated downloads). Slow, so only run on request.
+#[tauri::command]
+pub async fn recompute_duration(file_path: String) -> Result<f64, PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        // Summed per buffer, since a concatenated file can change sample rate part way
+        let mut duration = 0.0f64;
+        let stream = decode_interleaved(path, |samples, channels, sample_rate| {
+            if sample_rate > 0 {
+                duration += (samples.len() / channels) as f64 / sample_rate as f64;
+            }
+            ControlFlow::Continue(())
+        })?;
+
+        if stream.frames_decoded == 0 {
+            return Err(PlayerError::Decode("No audio could be decoded".to_string()));
+        }
+
+        println!("Recomputed duration of {}: {:.3}s", file_path, duration);
+        Ok(duration)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// RMS level of each channel over consecutive `window_ms` windows, for driving VU meters in
+// sync with playback. Outer vec is channels, inner is windows; levels are linear 0.0-1.0.
+#[tauri::command]
+pub async fn get_rms_envelope(
+    file_path: String,
+    window_ms: u32,
+) -> Result<Vec<Vec<f32>>, PlayerError> {
+    if !(1..=10_000).contains(&window_ms) {
+        return Err(PlayerError::InvalidInput(format!(
+            "Window must be between 1 and 10000 ms, got {}",
+            window_ms
+        )));
+    }
+
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let mut levels: Vec<Vec<f32>> = Vec::new();
+        let mut sums: Vec<f64> = Vec::new();
+        let mut window_frames = 0usize;
+        let mut frames_in_window = 0usize;
+
+        decode_interleaved(path, |samples, channels, sample_rate| {
+            // Window length is only known once the first packet tells us the sample rate
+            if levels.is_empty() {
+                levels = vec![Vec::new(); channels];
+                sums = vec![0.0; channels];
+                window_frames = ((sample_rate as u64 * window_ms as u64) / 1000).max(1) as usize;
+            }
+
+            for frame in samples.chunks(channels) {
+                for (sum, &sample) in sums.iter_mut().zip(frame) {
+                    *sum += (sample as f64) * (sample as f64);
+                }
+                frames_in_window += 1;
+
+                if frames_in_window == window_frames {
+                    for (channel, sum) in levels.iter_mut().zip(sums.iter_mut()) {
+                        channel.push((*sum / window_frames as f64).sqrt() as f32);
+                        *sum = 0.0;
+                    }
+                    frames_in_window = 0;
+                }
+            }
+            ControlFlow::Continue(())
+        })?;
+
+        // Whatever is left at the end still gets a (shorter) window
+        if frames_in_window > 0 {
+            for (channel, sum) in levels.iter_mut().zip(&sums) {
+                channel.push((sum / frames_in_window as f64).sqrt() as f32);
+            }
+        }
+
+        Ok(levels)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Downmixed mono samples (and their sample rate) from a `seconds` long window in the
+// middle of the track, skipping intros and outros that rarely represent the song
+fn decode_mono_window(path: &Path, seconds: f64) -> Result<(Vec<f32>, u32), PlayerError> {
+    let duration = read_track_metadata(path.to_string_lossy().to_string(), false, true).duration;
+    let start = if duration > seconds {
+        (duration - seconds) / 2.0
+    } else {
+        0.0
+    };
+
+    let mut mono: Vec<f32> = Vec::new();
+    let mut rate = 0u32;
+    decode_interleaved_from(path, start, |samples, channels, sample_rate| {
+        rate = sample_rate;
+        mono.extend(
+            samples
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+        if mono.len() as f64 >= seconds * sample_rate as f64 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })?;
+
+    if mono.is_empty() || rate == 0 {
+        return Err(PlayerError::Decode("No audio could be decoded".to_string()));
+    }
+    Ok((mono, rate))
+}
+
+// Estimate the tempo of a track from a 60-second window. Onsets come from spectral flux
+// (how much louder each frequency got since the previous frame); the beat period is the
+// lag where that onset curve best lines up with itself, weighted towards ~120 BPM so a
+// half- or double-time reading only wins when it's clearly stronger.
+#[tauri::command]
+pub async fn detect_bpm(file_path: String) -> Result<f64, PlayerError> {
+    use rustfft::{num_complex::Complex, FftPlanner};
+
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        const WINDOW_SECS: f64 = 60.0;
+        const FRAME: usize = 1024;
+        const HOP: usize = 512;
+        const MIN_BPM: f64 = 60.0;
+        const MAX_BPM: f64 = 200.0;
+
+        let (mono, sample_rate) = decode_mono_window(path, WINDOW_SECS)?;
+        if mono.len() < FRAME * 16 {
+            return Err(PlayerError::Decode(
+                "Track is too short to detect a tempo".to_string(),
+            ));
+        }
+
+        let fft = FftPlanner::<f32>::new().plan_fft_forward(FRAME);
+        let window: Vec<f32> = (0..FRAME)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FRAME as f32).cos())
+            .collect();
+        let mut buffer = vec![Complex::new(0.0f32, 0.0); FRAME];
+        let mut previous = vec![0.0f32; FRAME / 2];
+        let mut flux = Vec::with_capacity(mono.len() / HOP);
+
+        for start in (0..mono.len() - FRAME).step_by(HOP) {
+            for (i, slot) in buffer.iter_mut().enumerate() {
+                *slot = Complex::new(mono[start + i] * window[i], 0.0);
+            }
+            fft.process(&mut buffer);
+
+            // Log magnitudes, so quiet hi-hats count as well as loud kicks
+            let mut onset = 0.0f32;
+            for (bin, prev) in buffer[..FRAME / 2].iter().zip(previous.iter_mut()) {
+                let magnitude = (1.0 + 1000.0 * bin.norm()).ln();
+                onset += (magnitude - *prev).max(0.0);
+                *prev = magnitude;
+            }
+            flux.push(onset);
+        }
+        // The first frame has nothing to compare against
+        flux.remove(0);
+
+        let mean = flux.iter().sum::<f32>() / flux.len() as f32;
+        let onsets: Vec<f32> = flux.iter().map(|f| (f - mean).max(0.0)).collect();
+
+        // Onset frames per second, and the lag range covering MIN_BPM..MAX_BPM
+        let frame_rate = sample_rate as f64 / HOP as f64;
+        let min_lag = (60.0 * frame_rate / MAX_BPM).floor().max(1.0) as usize;
+        let max_lag = ((60.0 * frame_rate / MIN_BPM).ceil() as usize).min(onsets.len() / 2);
+        if min_lag >= max_lag {
+            return Err(PlayerError::Decode(
+                "Track is too short to detect a tempo".to_string(),
+            ));
+        }
+
+        let autocorrelation = |lag: usize| -> f64 {
+            let sum: f64 = onsets[..onsets.len() - lag]
+                .iter()
+                .zip(&onsets[lag..])
+                .map(|(a, b)| (*a as f64) * (*b as f64))
+                .sum();
+            sum / (onsets.len() - lag) as f64
+        };
+        let scores: Vec<f64> = (min_lag..=max_lag).map(autocorrelation).collect();
+
+        let weighted = |i: usize| -> f64 {
+            let bpm = 60.0 * frame_rate / (min_lag + i) as f64;
+            // Log-Gaussian preference for tempos near 120 BPM, about an octave wide
+            let octaves = (bpm / 120.0).log2();
+            scores[i] * (-0.5 * (octaves / 0.9).powi(2)).exp()
+        };
+        let best = (0..scores.len())
+            .max_by(|&a, &b| weighted(a).total_cmp(&weighted(b)))
+            .unwrap_or(0);
+        if scores[best] <= 0.0 {
+            return Err(PlayerError::Decode("No clear beat found".to_string()));
+        }
+
+        // Parabolic interpolation between neighbouring lags for a sub-frame period
+        let mut lag = (min_lag + best) as f64;
+        if best > 0 && best + 1 < scores.len() {
+            let (left, centre, right) = (scores[best - 1], scores[best], scores[best + 1]);
+            let denominator = left - 2.0 * centre + right;
+            if denominator.abs() > f64::EPSILON {
+                lag += 0.5 * (left - right) / denominator;
+            }
+        }
+
+        let bpm = 60.0 * frame_rate / lag;
+        let bpm = (bpm * 10.0).round() / 10.0;
+        println!("Detected tempo of {}: {} BPM", file_path, bpm);
+        Ok(bpm)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Krumhansl-Kessler key profiles: how strongly each scale degree (from the tonic up in
+// semitones) is felt to belong to a major or minor key
+const MAJOR_PROFILE: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+pub(crate) const PITCH_CLASSES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+// Pearson correlation between a chroma vector and a profile rotated to start on `tonic`
+fn profile_correlation(chroma: &[f64; 12], profile: &[f64; 12], tonic: usize) -> f64 {
+    let rotated: Vec<f64> = (0..12).map(|i| profile[(i + 12 - tonic) % 12]).collect();
+    let chroma_mean = chroma.iter().sum::<f64>() / 12.0;
+    let profile_mean = rotated.iter().sum::<f64>() / 12.0;
+
+    let mut covariance = 0.0;
+    let mut chroma_var = 0.0;
+    let mut profile_var = 0.0;
+    for (c, p) in chroma.iter().zip(&rotated) {
+        covariance += (c - chroma_mean) * (p - profile_mean);
+        chroma_var += (c - chroma_mean).powi(2);
+        profile_var += (p - profile_mean).powi(2);
+    }
+    if chroma_var <= 0.0 || profile_var <= 0.0 {
+        return 0.0;
+    }
+    covariance / (chroma_var * profile_var).sqrt()
+}
+
+// Estimate the musical key from a 60-second window: fold the spectrum into the 12 pitch
+// classes (a chroma vector) and pick the major or minor key profile it matches best.
+// Returns standard notation with sharps, e.g. "Am" or "C#".
+#[tauri::command]
+pub async fn detect_key(file_path: String) -> Result<String, PlayerError> {
+    use rustfft::{num_complex::Complex, FftPlanner};
+
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        const WINDOW_SECS: f64 = 60.0;
+        const FRAME: usize = 8192;
+        const HOP: usize = 4096;
+        // Below this the bins are too coarse to tell semitones apart; above it is mostly
+        // overtones and percussion
+        const MIN_FREQ: f64 = 55.0;
+        const MAX_FREQ: f64 = 5000.0;
+
+        let (mono, sample_rate) = decode_mono_window(path, WINDOW_SECS)?;
+        if mono.len() < FRAME * 4 {
+            return Err(PlayerError::Decode(
+                "Track is too short to detect a key".to_string(),
+            ));
+        }
+
+        // Pitch class of every FFT bin in range, worked out once
+        let bin_hz = sample_rate as f64 / FRAME as f64;
+        let bin_classes: Vec<(usize, usize)> = (1..FRAME / 2)
+            .filter_map(|bin| {
+                let freq = bin as f64 * bin_hz;
+                if !(MIN_FREQ..=MAX_FREQ).contains(&freq) {
+                    return None;
+                }
+                // MIDI note number; 69 is A440 and note 0 is a C
+                let midi = 69.0 + 12.0 * (freq / 440.0).log2();
+                Some((bin, (midi.round() as i64).rem_euclid(12) as usize))
+            })
+            .collect();
+
+        let fft = FftPlanner::<f32>::new().plan_fft_forward(FRAME);
+        let window: Vec<f32> = (0..FRAME)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FRAME as f32).cos())
+            .collect();
+        let mut buffer = vec![Complex::new(0.0f32, 0.0); FRAME];
+        let mut chroma = [0.0f64; 12];
+
+        for start in (0..mono.len() - FRAME).step_by(HOP) {
+            for (i, slot) in buffer.iter_mut().enumerate() {
+                *slot = Complex::new(mono[start + i] * window[i], 0.0);
+            }
+            fft.process(&mut buffer);
+            for &(bin, class) in &bin_classes {
+                chroma[class] += buffer[bin].norm() as f64;
+            }
+        }
+
+        let mut best = (f64::MIN, String::new());
+        for tonic in 0..12 {
+            for (profile, suffix) in [(&MAJOR_PROFILE, ""), (&MINOR_PROFILE, "m")] {
+                let score = profile_correlation(&chroma, profile, tonic);
+                if score > best.0 {
+                    best = (score, format!("{}{}", PITCH_CLASSES[tonic], suffix));
+                }
+            }
+        }
+
+        if best.0 <= 0.0 {
+            return Err(PlayerError::Decode("No clear key found".to_string()));
+        }
+
+        println!(
+            "Detected key of {}: {} (r = {:.2})",
+            file_path, best.1, best.0
+        );
+        Ok(best.1)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
@@ -0,0 +1,279 @@
+// src-tauri/src/cache.rs
+use crate::error::PlayerError;
+use crate::media_player::Track;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Manager};
+
+// First 16 hex digits of the data's SHA-256, for naming files on disk. Unlike
+// DefaultHasher it's the same in every Rust release, so names survive an app update.
+pub(crate) fn file_key(data: &[u8]) -> String {
+    let mut key = format!("{:x}", Sha256::digest(data));
+    key.truncate(16);
+    key
+}
+
+// Modification time (nanoseconds since epoch) and size of the file on disk
+fn file_stamp(file_path: &str) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(Path::new(file_path)).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((modified.as_nanos() as u64, metadata.len()))
+}
+
+// Bump whenever Track gains a field or a reader changes what it reports, so entries
+// written by an older build are read again instead of coming back with the new fields empty
+const CACHE_VERSION: u32 = 1;
+
+// One cached track, valid only while the file's mtime and size are unchanged
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+struct CacheEntry {
+    // Entries from before versioning have none, which reads as 0 and never matches
+    #[serde(default)]
+    version: u32,
+    path: String,
+    modified_nanos: u64,
+    size: u64,
+    enable_gapless: bool,
+    // Read without artwork, so it can't satisfy a request that wants the cover
+    #[serde(default)]
+    skip_cover: bool,
+    track: Track,
+}
+
+// Metadata cache stored as one small JSON file per track, so updating a single
+// entry never means rewriting the whole library's worth of cover art
+pub struct MetadataCache {
+    dir: PathBuf,
+}
+
+impl MetadataCache {
+    pub fn new(app: &AppHandle) -> Result<Self, PlayerError> {
+        let dir = app.path().app_data_dir()?.join("metadata_cache");
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+        }
+        Ok(Self { dir })
+    }
+
+    fn entry_path(&self, file_path: &str) -> PathBuf {
+        let key = file_key(file_path.as_bytes());
+        self.dir.join(format!("{}.json", key))
+    }
+
+    pub fn get(&self, file_path: &str, enable_gapless: bool, skip_cover: bool) -> Option<Track> {
+        let (modified_nanos, size) = file_stamp(file_path)?;
+        let json = fs::read_to_string(self.entry_path(file_path)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&json).ok()?;
+
+        // Hash collisions, stale entries and old cache versions all just count as a miss
+        if entry.version == CACHE_VERSION
+            && entry.path == file_path
+            && entry.modified_nanos == modified_nanos
+            && entry.size == size
+            && entry.enable_gapless == enable_gapless
+            && (skip_cover || !entry.skip_cover)
+        {
+            let mut track = entry.track;
+            if skip_cover {
+                track.cover_image = None;
+            }
+            Some(track)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&self, track: &Track, enable_gapless: bool, skip_cover: bool) {
+        let Some((modified_nanos, size)) = file_stamp(&track.path) else {
+            return;
+        };
+
+        let entry = CacheEntry {
+            version: CACHE_VERSION,
+            path: track.path.clone(),
+            modified_nanos,
+            size,
+            enable_gapless,
+            skip_cover,
+            track: track.clone(),
+        };
+
+        let write = || -> Result<(), PlayerError> {
+            let json = serde_json::to_string(&entry)?;
+            fs::write(self.entry_path(&track.path), json)?;
+            Ok(())
+        };
+        if let Err(e) = write() {
+            eprintln!("⚠️ Failed to write metadata cache for {}: {}", track.path, e);
+        }
+    }
+
+    pub fn clear(&self) -> Result<(), PlayerError> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        fs::create_dir_all(&self.dir)?;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn clear_metadata_cache(app: AppHandle) -> Result<(), PlayerError> {
+    println!("Clearing metadata cache");
+    match app.try_state::<MetadataCache>() {
+        Some(cache) => cache.clear(),
+        None => MetadataCache::new(&app)?.clear(),
+    }
+}
+
+// Waveform peaks for one file at one resolution
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+struct WaveformEntry {
+    path: String,
+    modified_nanos: u64,
+    size: u64,
+    num_buckets: usize,
+    peaks: Vec<f32>,
+}
+
+// Computed waveforms keyed by path hash and bucket count. The entry records the file's
+// mtime and size, so an edited file overwrites its old entry instead of piling up new ones.
+pub struct WaveformCache {
+    dir: PathBuf,
+}
+
+impl WaveformCache {
+    pub fn new(app: &AppHandle) -> Result<Self, PlayerError> {
+        let dir = app.path().app_data_dir()?.join("waveforms");
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+        }
+        Ok(Self { dir })
+    }
+
+    fn entry_path(&self, file_path: &str, num_buckets: usize) -> PathBuf {
+        let key = file_key(file_path.as_bytes());
+        self.dir.join(format!("{}_{}.json", key, num_buckets))
+    }
+
+    pub fn get(&self, file_path: &str, num_buckets: usize) -> Option<Vec<f32>> {
+        let (modified_nanos, size) = file_stamp(file_path)?;
+        let entry_path = self.entry_path(file_path, num_buckets);
+        let json = fs::read_to_string(entry_path).ok()?;
+        let entry: WaveformEntry = serde_json::from_str(&json).ok()?;
+
+        if entry.path == file_path
+            && entry.modified_nanos == modified_nanos
+            && entry.size == size
+            && entry.num_buckets == num_buckets
+            && entry.peaks.len() == num_buckets
+        {
+            Some(entry.peaks)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&self, file_path: &str, num_buckets: usize, peaks: &[f32]) {
+        let Some((modified_nanos, size)) = file_stamp(file_path) else {
+            return;
+        };
+
+        let entry = WaveformEntry {
+            path: file_path.to_string(),
+            modified_nanos,
+            size,
+            num_buckets,
+            peaks: peaks.to_vec(),
+        };
+
+        let entry_path = self.entry_path(file_path, num_buckets);
+        let write = || -> Result<(), PlayerError> {
+            let json = serde_json::to_string(&entry)?;
+            fs::write(&entry_path, json)?;
+            Ok(())
+        };
+        if let Err(e) = write() {
+            eprintln!("⚠️ Failed to write waveform cache for {}: {}", file_path, e);
+        }
+    }
+
+    pub fn clear(&self) -> Result<(), PlayerError> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        fs::create_dir_all(&self.dir)?;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn clear_waveform_cache(app: AppHandle) -> Result<(), PlayerError> {
+    println!("Clearing waveform cache");
+    WaveformCache::new(&app)?.clear()
+}
+
+// Audio fingerprint for one file, valid while its mtime and size are unchanged
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+struct FingerprintEntry {
+    modified_nanos: u64,
+    size: u64,
+    fingerprint: String,
+}
+
+// Fingerprints are tiny, so unlike the metadata cache they all live in one JSON file
+// that's loaded once per library scan and written back at the end
+pub struct FingerprintCache {
+    file: PathBuf,
+    entries: HashMap<String, FingerprintEntry>,
+}
+
+impl FingerprintCache {
+    pub fn load(app: &AppHandle) -> Result<Self, PlayerError> {
+        let dir = app.path().app_data_dir()?;
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+        }
+        let file = dir.join("fingerprints.json");
+
+        // A corrupt cache just means recomputing everything
+        let entries = fs::read_to_string(&file)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Ok(Self { file, entries })
+    }
+
+    pub fn get(&self, file_path: &str) -> Option<String> {
+        let (modified_nanos, size) = file_stamp(file_path)?;
+        self.entries
+            .get(file_path)
+            .filter(|entry| entry.modified_nanos == modified_nanos && entry.size == size)
+            .map(|entry| entry.fingerprint.clone())
+    }
+
+    pub fn insert(&mut self, file_path: &str, fingerprint: &str) {
+        let Some((modified_nanos, size)) = file_stamp(file_path) else {
+            return;
+        };
+        self.entries.insert(
+            file_path.to_string(),
+            FingerprintEntry {
+                modified_nanos,
+                size,
+                fingerprint: fingerprint.to_string(),
+            },
+        );
+    }
+
+    // Drop entries for files that no longer exist so the cache doesn't grow forever
+    pub fn save(&mut self) -> Result<(), PlayerError> {
+        self.entries.retain(|path, _| Path::new(path).exists());
+        let json = serde_json::to_string(&self.entries)?;
+        fs::write(&self.file, json)?;
+        Ok(())
+    }
+}
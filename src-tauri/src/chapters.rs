@@ -0,0 +1,560 @@
+// src-tauri/src/chapters.rs
+use crate::error::PlayerError;
+use crate::media_player::sniff_format;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct Chapter {
+    pub title: String,
+    pub start: f64, // seconds
+    pub end: f64,
+}
+
+// CHAP frames from an ID3v2 tag. Times are milliseconds; the title is the TIT2 sub-frame.
+fn id3_chapters(path: &Path) -> Result<Vec<Chapter>, PlayerError> {
+    let tag = match id3::Tag::read_from_path(path) {
+        Ok(tag) => tag,
+        Err(id3::Error { kind: id3::ErrorKind::NoTag, .. }) => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(PlayerError::MetadataRead(format!(
+                "Failed to read ID3 tags: {}",
+                e
+            )))
+        }
+    };
+
+    Ok(tag
+        .chapters()
+        .map(|chapter| {
+            let title = chapter
+                .frames
+                .iter()
+                .find(|frame| frame.id() == "TIT2")
+                .and_then(|frame| frame.content().text())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| chapter.element_id.clone());
+            Chapter {
+                title,
+                start: chapter.start_time as f64 / 1000.0,
+                end: chapter.end_time as f64 / 1000.0,
+            }
+        })
+        .collect())
+}
+
+fn be_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn be_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+// Child boxes of an MP4 box body as (type, body) pairs
+fn mp4_boxes(mut data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut boxes = Vec::new();
+    while data.len() >= 8 {
+        let Some(size) = be_u32(data, 0) else {
+            break;
+        };
+        let (header, size) = match size {
+            0 => (8, data.len()),
+            1 => match be_u64(data, 8) {
+                Some(size) => (16, size as usize),
+                None => break,
+            },
+            size => (8, size as usize),
+        };
+        if size < header || size > data.len() {
+            break;
+        }
+        let kind = [data[4], data[5], data[6], data[7]];
+        boxes.push((kind, &data[header..size]));
+        data = &data[size..];
+    }
+    boxes
+}
+
+fn mp4_child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    mp4_boxes(data).into_iter().find(|(k, _)| k == kind).map(|(_, body)| body)
+}
+
+fn mp4_path<'a>(data: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+    path.iter().try_fold(data, |body, kind| mp4_child(body, kind))
+}
+
+// Read the top-level moov box into memory; mdat is skipped over with a seek
+fn read_moov(file: &mut File) -> Option<Vec<u8>> {
+    let file_len = file.metadata().ok()?.len();
+    let mut offset = 0u64;
+    while offset + 8 <= file_len {
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let mut header = [0u8; 16];
+        file.read_exact(&mut header[..8]).ok()?;
+        let (header_len, size) = match be_u32(&header, 0)? {
+            0 => (8, file_len - offset),
+            1 => {
+                file.read_exact(&mut header[8..]).ok()?;
+                (16, be_u64(&header, 8)?)
+            }
+            size => (8, size as u64),
+        };
+        if size < header_len {
+            return None;
+        }
+        if &header[4..8] == b"moov" {
+            // Far beyond any real moov; guards against a corrupt size
+            let body_len = (size - header_len).min(256 * 1024 * 1024) as usize;
+            let mut body = vec![0u8; body_len];
+            file.read_exact(&mut body).ok()?;
+            return Some(body);
+        }
+        offset += size;
+    }
+    None
+}
+
+// Movie duration in seconds from mvhd
+fn mp4_movie_duration(moov: &[u8]) -> Option<f64> {
+    let mvhd = mp4_child(moov, b"mvhd")?;
+    let (timescale, duration) = if mvhd.first()? == &1 {
+        (be_u32(mvhd, 20)?, be_u64(mvhd, 24)?)
+    } else {
+        (be_u32(mvhd, 12)?, be_u32(mvhd, 16)? as u64)
+    };
+    (timescale > 0).then(|| duration as f64 / timescale as f64)
+}
+
+// Nero-style chapter list in moov/udta/chpl: start times in 100ns units
+fn mp4_nero_chapters(moov: &[u8]) -> Option<Vec<(f64, String)>> {
+    let chpl = mp4_path(moov, &[b"udta", b"chpl"])?;
+    let mut pos = if chpl.first()? != &0 { 8 } else { 4 };
+    let count = *chpl.get(pos)? as usize;
+    pos += 1;
+
+    let mut chapters = Vec::with_capacity(count);
+    for _ in 0..count {
+        let start = be_u64(chpl, pos)? as f64 / 10_000_000.0;
+        let len = *chpl.get(pos + 8)? as usize;
+        let title = String::from_utf8_lossy(chpl.get(pos + 9..pos + 9 + len)?).to_string();
+        chapters.push((start, title));
+        pos += 9 + len;
+    }
+    Some(chapters)
+}
+
+// QuickTime chapters: a text track referenced by another track's tref/chap, with one
+// sample per chapter. Returns (start, title) pairs plus the text track's total length.
+fn mp4_track_chapters(file: &mut File, moov: &[u8]) -> Option<(Vec<(f64, String)>, f64)> {
+    let traks: Vec<&[u8]> = mp4_boxes(moov)
+        .into_iter()
+        .filter(|(k, _)| k == b"trak")
+        .map(|(_, body)| body)
+        .collect();
+
+    let chapter_id = traks
+        .iter()
+        .find_map(|trak| mp4_path(trak, &[b"tref", b"chap"]))
+        .and_then(|chap| be_u32(chap, 0))?;
+    let trak = traks.iter().find(|trak| {
+        mp4_child(trak, b"tkhd").and_then(|tkhd| {
+            // Track ID follows the two timestamps, which are 64-bit in version 1
+            if tkhd.first()? == &1 {
+                be_u32(tkhd, 20)
+            } else {
+                be_u32(tkhd, 12)
+            }
+        }) == Some(chapter_id)
+    })?;
+
+    let mdia = mp4_child(trak, b"mdia")?;
+    let mdhd = mp4_child(mdia, b"mdhd")?;
+    let timescale = if mdhd.first()? == &1 { be_u32(mdhd, 20)? } else { be_u32(mdhd, 12)? };
+    if timescale == 0 {
+        return None;
+    }
+    let stbl = mp4_path(mdia, &[b"minf", b"stbl"])?;
+
+    // Sample start times from the time-to-sample table
+    let stts = mp4_child(stbl, b"stts")?;
+    let mut starts = Vec::new();
+    let mut time = 0u64;
+    for i in 0..be_u32(stts, 4)? as usize {
+        let count = be_u32(stts, 8 + i * 8)?;
+        let delta = be_u32(stts, 12 + i * 8)? as u64;
+        // A chapter track has a handful of samples; the cap only guards corrupt counts
+        for _ in 0..count.min(10_000) {
+            starts.push(time);
+            time += delta;
+        }
+    }
+    let total = time as f64 / timescale as f64;
+
+    let stsz = mp4_child(stbl, b"stsz")?;
+    let uniform_size = be_u32(stsz, 4)?;
+    let sample_size = |i: usize| -> Option<u32> {
+        if uniform_size != 0 {
+            Some(uniform_size)
+        } else {
+            be_u32(stsz, 12 + i * 4)
+        }
+    };
+
+    let chunk_offsets: Vec<u64> = if let Some(stco) = mp4_child(stbl, b"stco") {
+        (0..be_u32(stco, 4)? as usize)
+            .filter_map(|i| be_u32(stco, 8 + i * 4).map(u64::from))
+            .collect()
+    } else {
+        let co64 = mp4_child(stbl, b"co64")?;
+        (0..be_u32(co64, 4)? as usize)
+            .filter_map(|i| be_u64(co64, 8 + i * 8))
+            .collect()
+    };
+
+    // Walk chunks using the sample-to-chunk runs to find each sample's file offset
+    let stsc = mp4_child(stbl, b"stsc")?;
+    let runs: Vec<(usize, u32)> = (0..be_u32(stsc, 4)? as usize)
+        .filter_map(|i| {
+            let first_chunk = be_u32(stsc, 8 + i * 12)? as usize;
+            Some((first_chunk.checked_sub(1)?, be_u32(stsc, 12 + i * 12)?))
+        })
+        .collect();
+
+    let mut chapters = Vec::new();
+    let mut sample = 0;
+    for (chunk, &chunk_offset) in chunk_offsets.iter().enumerate() {
+        let per_chunk = runs
+            .iter()
+            .rev()
+            .find(|(first, _)| *first <= chunk)
+            .map_or(1, |(_, n)| *n);
+        let mut offset = chunk_offset;
+        for _ in 0..per_chunk {
+            let (Some(&start), Some(size)) = (starts.get(sample), sample_size(sample)) else {
+                break;
+            };
+            // Text sample: 16-bit length, then the title
+            let mut text = vec![0u8; size.min(64 * 1024) as usize];
+            file.seek(SeekFrom::Start(offset)).ok()?;
+            file.read_exact(&mut text).ok()?;
+            let len = be_u16(&text, 0).unwrap_or(0) as usize;
+            let title = text.get(2..2 + len).map(decode_chapter_text).unwrap_or_default();
+            chapters.push((start as f64 / timescale as f64, title));
+
+            offset += size as u64;
+            sample += 1;
+        }
+    }
+
+    Some((chapters, total))
+}
+
+// Chapter titles are UTF-8 unless they start with a UTF-16 BOM
+fn decode_chapter_text(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFE, 0xFF, rest @ ..] => {
+            let units: Vec<u16> =
+                rest.chunks_exact(2).map(|p| u16::from_be_bytes([p[0], p[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        [0xFF, 0xFE, rest @ ..] => {
+            let units: Vec<u16> =
+                rest.chunks_exact(2).map(|p| u16::from_le_bytes([p[0], p[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(bytes).to_string(),
+    }
+    .trim()
+    .to_string()
+}
+
+// .aac is usually a bare ADTS stream, with no MP4 boxes to hold chapters or tags
+fn is_adts(path: &Path) -> bool {
+    sniff_format(path) == Some("aac")
+}
+
+fn mp4_chapters(path: &Path) -> Result<Vec<Chapter>, PlayerError> {
+    let mut file = File::open(path)?;
+    let Some(moov) = read_moov(&mut file) else {
+        return Err(PlayerError::MetadataRead(format!(
+            "No moov box found in {}",
+            path.display()
+        )));
+    };
+    let movie_duration = mp4_movie_duration(&moov);
+
+    // Prefer the QuickTime chapter track (what iTunes and most encoders write)
+    let (starts, total) = match mp4_track_chapters(&mut file, &moov) {
+        Some((starts, total)) if !starts.is_empty() => (starts, movie_duration.unwrap_or(total)),
+        _ => match mp4_nero_chapters(&moov) {
+            Some(starts) => (starts, movie_duration.unwrap_or(0.0)),
+            None => return Ok(Vec::new()),
+        },
+    };
+
+    // Each chapter runs until the next one starts; the last one until the end of the file
+    let chapters = starts
+        .iter()
+        .enumerate()
+        .map(|(i, (start, title))| Chapter {
+            title: if title.is_empty() { format!("Chapter {}", i + 1) } else { title.clone() },
+            start: *start,
+            end: starts.get(i + 1).map_or(total, |(next, _)| *next).max(*start),
+        })
+        .collect();
+    Ok(chapters)
+}
+
+// Chapter markers for audiobooks and mixes: CHAP frames for MP3, the chapter track
+// (or Nero chpl list) for M4A. Files without chapters give an empty list.
+#[tauri::command]
+pub async fn get_chapters(file_path: String) -> Result<Vec<Chapter>, PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let extension = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        let mut chapters = match extension.as_str() {
+            "mp3" => id3_chapters(path)?,
+            "aac" if is_adts(path) => Vec::new(),
+            "m4a" | "m4b" | "mp4" | "aac" => mp4_chapters(path)?,
+            _ => Vec::new(),
+        };
+        chapters.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+        eprintln!("Found {} chapters in {}", chapters.len(), file_path);
+        Ok(chapters)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// A DJ hot cue stored in the file by Serato
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct Marker {
+    pub name: String,
+    pub position_secs: f64,
+    // "#RRGGBB"
+    pub color: Option<String>,
+}
+
+// Serato base64 is wrapped across lines, usually unpadded, and sometimes has a stray
+// trailing character that makes the length impossible
+fn decode_serato_base64(text: &[u8]) -> Option<Vec<u8>> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let mut cleaned: Vec<u8> = text
+        .iter()
+        .copied()
+        .take_while(|&b| b != 0)
+        .filter(|b| !b.is_ascii_whitespace() && *b != b'=')
+        .collect();
+    if cleaned.len() % 4 == 1 {
+        cleaned.pop();
+    }
+    general_purpose::STANDARD_NO_PAD.decode(cleaned).ok()
+}
+
+// Body of a "Serato Markers2" object: a 0x01 0x01 version, then base64 of another version
+// followed by entries of (null-terminated type, u32 length, data). Only CUE entries are
+// hot cues; COLOR, BPMLOCK, LOOP and the rest are skipped.
+fn parse_serato_markers2(payload: &[u8]) -> Vec<Marker> {
+    let Some(decoded) = payload
+        .strip_prefix(&[0x01, 0x01])
+        .and_then(decode_serato_base64)
+    else {
+        return Vec::new();
+    };
+    let mut data = decoded.strip_prefix(&[0x01, 0x01]).unwrap_or(&decoded);
+
+    let mut markers = Vec::new();
+    while let Some(name_end) = data.iter().position(|&b| b == 0) {
+        let kind = &data[..name_end];
+        let Some(len) = be_u32(data, name_end + 1).map(|n| n as usize) else {
+            break;
+        };
+        let start = name_end + 5;
+        let Some(entry) = data.get(start..start + len) else {
+            break;
+        };
+        if kind.is_empty() {
+            break;
+        }
+
+        // 0x00, index, position in ms, 0x00, RGB, two zero bytes, null-terminated name
+        if kind == b"CUE" && entry.len() >= 13 {
+            let index = entry[1];
+            let position_ms = be_u32(entry, 2).unwrap_or(0);
+            let name_bytes = entry[12..].split(|&b| b == 0).next().unwrap_or_default();
+            let name = String::from_utf8_lossy(name_bytes).trim().to_string();
+            markers.push(Marker {
+                name: if name.is_empty() {
+                    format!("Cue {}", index + 1)
+                } else {
+                    name
+                },
+                position_secs: position_ms as f64 / 1000.0,
+                color: Some(format!("#{:02X}{:02X}{:02X}", entry[7], entry[8], entry[9])),
+            });
+        }
+        data = &data[start + len..];
+    }
+    markers
+}
+
+// MP3: a GEOB frame described as "Serato Markers2"
+fn id3_markers(path: &Path) -> Result<Vec<Marker>, PlayerError> {
+    let tag = match id3::Tag::read_from_path(path) {
+        Ok(tag) => tag,
+        Err(id3::Error { kind: id3::ErrorKind::NoTag, .. }) => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(PlayerError::MetadataRead(format!(
+                "Failed to read ID3 tags: {}",
+                e
+            )))
+        }
+    };
+
+    Ok(tag
+        .encapsulated_objects()
+        .find(|object| object.description == "Serato Markers2")
+        .map(|object| parse_serato_markers2(&object.data))
+        .unwrap_or_default())
+}
+
+// M4A: the ----:com.serato.dj:markersv2 atom holds base64 of a small header
+// ("application/octet-stream", "Serato Markers2") followed by the same body as the GEOB
+fn mp4_markers(path: &Path) -> Result<Vec<Marker>, PlayerError> {
+    const HEADER_END: &[u8] = b"Serato Markers2\0";
+
+    let tag = mp4ameta::Tag::read_from_path(path)
+        .map_err(|e| PlayerError::MetadataRead(format!("Failed to read MP4 tags: {}", e)))?;
+    let text = tag.data().find_map(|(ident, data)| match ident {
+        mp4ameta::DataIdent::Freeform { mean, name }
+            if mean == "com.serato.dj" && name.eq_ignore_ascii_case("markersv2") =>
+        {
+            data.string()
+        }
+        _ => None,
+    });
+
+    let Some(decoded) = text.and_then(|t| decode_serato_base64(t.as_bytes())) else {
+        return Ok(Vec::new());
+    };
+    let payload = decoded
+        .windows(HEADER_END.len())
+        .position(|w| w == HEADER_END)
+        .map_or(&decoded[..], |i| &decoded[i + HEADER_END.len()..]);
+    Ok(parse_serato_markers2(payload))
+}
+
+// Hot cues saved into the file by Serato DJ, sorted by position. Traktor keeps its cues
+// in its own collection file rather than the track, so those can't be read here. Files
+// without markers give an empty list.
+#[tauri::command]
+pub async fn get_markers(file_path: String) -> Result<Vec<Marker>, PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let extension = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        let mut markers = match extension.as_str() {
+            "mp3" => id3_markers(path)?,
+            "aac" if is_adts(path) => Vec::new(),
+            "m4a" | "mp4" | "aac" => mp4_markers(path)?,
+            _ => Vec::new(),
+        };
+        markers.sort_by(|a, b| a.position_secs.total_cmp(&b.position_secs));
+
+        eprintln!("Found {} markers in {}", markers.len(), file_path);
+        Ok(markers)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Markers2 body as Serato writes it: version, then line-wrapped unpadded base64 of a
+    // COLOR entry, two CUE entries (the second unnamed) and a BPMLOCK entry, then padding
+    const MARKERS2: &[u8] = b"\x01\x01\
+        AQFDT0xPUgAAAAAEAP///0NVRQAAAAARAAAAAAXcAMwAAAAARHJvcABDVUUAAAAADQADAADz\n\
+        KgAAiMwAAABCUE1MT0NLAAAAAAEAAA\0\0\0";
+
+    #[test]
+    fn parses_serato_hot_cues() {
+        let markers = parse_serato_markers2(MARKERS2);
+
+        assert_eq!(markers.len(), 2);
+        assert_eq!(markers[0].name, "Drop");
+        assert_eq!(markers[0].position_secs, 1.5);
+        assert_eq!(markers[0].color.as_deref(), Some("#CC0000"));
+        // Unnamed cues are labelled by their 1-based slot
+        assert_eq!(markers[1].name, "Cue 4");
+        assert_eq!(markers[1].position_secs, 62.25);
+        assert_eq!(markers[1].color.as_deref(), Some("#0088CC"));
+    }
+
+    #[test]
+    fn tolerates_a_stray_trailing_base64_character() {
+        // Cut the base64 to a multiple of 4 (dropping only the end marker) plus one
+        // character, a length that can't be decoded as is
+        let mut payload = MARKERS2[..MARKERS2.len() - 5].to_vec();
+        payload.push(b'A');
+        assert_eq!(parse_serato_markers2(&payload).len(), 2);
+    }
+
+    #[test]
+    fn adts_aac_has_no_chapters_or_markers() {
+        // One ADTS frame header (AAC LC, 44.1kHz, stereo) and an empty body
+        let mut data = vec![0xFF, 0xF1, 0x50, 0x80, 0x02, 0x1F, 0xFC];
+        data.resize(64, 0);
+        let dir = std::env::temp_dir().join(format!("pancake-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stream.aac");
+        std::fs::write(&path, data).unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let chapters = tauri::async_runtime::block_on(get_chapters(path.clone())).unwrap();
+        assert!(chapters.is_empty());
+        let markers = tauri::async_runtime::block_on(get_markers(path)).unwrap();
+        assert!(markers.is_empty());
+    }
+
+    #[test]
+    fn ignores_payloads_without_the_version_header() {
+        assert!(parse_serato_markers2(&MARKERS2[2..]).is_empty());
+        assert!(parse_serato_markers2(b"").is_empty());
+    }
+}
@@ -0,0 +1,615 @@
+// src-tauri/src/cover_art.rs
+use crate::cache::file_key;
+use crate::error::PlayerError;
+use crate::media_player::{create_backup, tag_writer, TagWriter};
+use base64::{engine::general_purpose, Engine as _};
+use id3::TagLike;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tauri::{AppHandle, Manager};
+
+// Largest width or height accepted for artwork being embedded. Anything bigger is almost
+// certainly a mistake and would bloat every copy of the file.
+static MAX_COVER_DIMENSION: AtomicU32 = AtomicU32::new(8000);
+
+// Map lofty's picture MIME type to a string usable in a data URL
+pub(crate) fn lofty_mime_type(mime_type: &lofty::MimeType) -> &'static str {
+    match mime_type {
+        lofty::MimeType::Png => "image/png",
+        lofty::MimeType::Jpeg => "image/jpeg",
+        lofty::MimeType::Gif => "image/gif",
+        lofty::MimeType::Bmp => "image/bmp",
+        lofty::MimeType::Tiff => "image/tiff",
+        _ => "image/jpeg", // Default to jpeg as fallback
+    }
+}
+
+// Read the first embedded picture as raw bytes plus its MIME type
+pub(crate) fn read_embedded_picture(path: &Path) -> Option<(Vec<u8>, String)> {
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    match extension.as_str() {
+        "mp3" | "aiff" | "aif" => {
+            let tag = id3::Tag::read_from_path(path).ok()?;
+            let picture = tag.pictures().next()?;
+            Some((picture.data.clone(), picture.mime_type.clone()))
+        }
+        "m4a" | "mp4" | "aac" => {
+            let tag = mp4ameta::Tag::read_from_path(path).ok()?;
+            let artwork = tag.artwork()?;
+            let mime_type = match artwork.fmt {
+                mp4ameta::ImgFmt::Png => "image/png",
+                mp4ameta::ImgFmt::Bmp => "image/bmp",
+                mp4ameta::ImgFmt::Jpeg => "image/jpeg",
+            };
+            Some((artwork.data.to_vec(), mime_type.to_string()))
+        }
+        _ => {
+            use lofty::{Probe, TaggedFileExt};
+
+            let tagged_file = Probe::open(path).and_then(|p| p.read()).ok()?;
+            tagged_file.tags().iter().find_map(|tag| {
+                tag.pictures().first().map(|picture| {
+                    (
+                        picture.data().to_vec(),
+                        lofty_mime_type(picture.mime_type()).to_string(),
+                    )
+                })
+            })
+        }
+    }
+}
+
+// Whether embedded art actually decodes. Formats this build has no decoder for (e.g.
+// TIFF) get the benefit of the doubt; only garbage and corrupt images fail.
+pub(crate) fn picture_decodes(data: &[u8]) -> bool {
+    match image::guess_format(data) {
+        Ok(format) if !format.reading_enabled() => true,
+        Ok(_) => image::load_from_memory(data).is_ok(),
+        Err(_) => false,
+    }
+}
+
+// Shrink an image so its longest side fits within max_dimension, re-encoded as JPEG
+fn downscale_image(data: &[u8], max_dimension: u32) -> Result<Option<Vec<u8>>, String> {
+    let img = image::load_from_memory(data).map_err(|e| format!("Failed to decode image: {}", e))?;
+
+    if img.width() <= max_dimension && img.height() <= max_dimension {
+        return Ok(None);
+    }
+
+    // JPEG has no alpha channel, so flatten to RGB before encoding
+    let thumbnail = image::DynamicImage::ImageRgb8(img.thumbnail(max_dimension, max_dimension).to_rgb8());
+    let mut buffer = Cursor::new(Vec::new());
+    thumbnail
+        .write_to(&mut buffer, image::ImageFormat::Jpeg)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+
+    Ok(Some(buffer.into_inner()))
+}
+
+// Build a data URL for embedded artwork. The base64 text goes straight into one buffer of
+// the final size, a chunk at a time, so a 40MB picture doesn't also need a separate
+// ~53MB encoded copy that then gets copied again by format!.
+pub(crate) fn cover_data_url(mime_type: &str, data: &[u8]) -> String {
+    // A multiple of 3 bytes, so no chunk but the last needs padding
+    const CHUNK_BYTES: usize = 3 * 64 * 1024;
+
+    let prefix = format!("data:{};base64,", mime_type);
+    let mut url = String::with_capacity(prefix.len() + data.len().div_ceil(3) * 4);
+    url.push_str(&prefix);
+    for chunk in data.chunks(CHUNK_BYTES) {
+        general_purpose::STANDARD.encode_string(chunk, &mut url);
+    }
+    url
+}
+
+// Re-encode a cover art data URL as progressively smaller JPEGs until it fits within
+// max_bytes. Returns None when the cover is already small enough or can't be decoded.
+pub(crate) fn shrink_cover_data_url(data_url: &str, max_bytes: usize) -> Option<String> {
+    let (_, encoded) = data_url.split_once(";base64,")?;
+    // Base64 is 4/3 the size of the data, so this skips the decode for small covers
+    if encoded.len() / 4 * 3 <= max_bytes {
+        return None;
+    }
+    let data = general_purpose::STANDARD.decode(encoded).ok()?;
+    if data.len() <= max_bytes {
+        return None;
+    }
+
+    let img = match image::load_from_memory(&data) {
+        Ok(img) => img,
+        Err(e) => {
+            eprintln!("⚠️ Could not decode cover art for recompression: {}", e);
+            return None;
+        }
+    };
+    // JPEG has no alpha channel, so flatten to RGB before encoding
+    let mut img = image::DynamicImage::ImageRgb8(img.to_rgb8());
+
+    let mut smallest: Option<Vec<u8>> = None;
+    loop {
+        for quality in [85u8, 70, 55, 40] {
+            let mut buffer = Vec::new();
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, quality);
+            if let Err(e) = img.write_with_encoder(encoder) {
+                eprintln!("⚠️ Failed to re-encode cover art: {}", e);
+                return None;
+            }
+            if buffer.len() <= max_bytes {
+                return Some(cover_data_url("image/jpeg", &buffer));
+            }
+            if smallest.as_ref().is_none_or(|s| buffer.len() < s.len()) {
+                smallest = Some(buffer);
+            }
+        }
+
+        // Lowest quality still doesn't fit, so halve the dimensions and try again
+        if img.width() <= 64 || img.height() <= 64 {
+            break;
+        }
+        img = img.thumbnail(img.width() / 2, img.height() / 2);
+    }
+
+    // Return the best effort if it's at least smaller than the original
+    smallest
+        .filter(|s| s.len() < data.len())
+        .map(|s| cover_data_url("image/jpeg", &s))
+}
+
+// Embedded artwork as bare base64, with its real format and size so the frontend can
+// build the data URL and reserve layout space
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct CoverArt {
+    pub data: String,
+    pub mime_type: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Format and pixel size read from the image header, without decoding the pixels
+fn image_info(data: &[u8]) -> Option<(Option<&'static str>, u32, u32)> {
+    let reader = image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?;
+    let mime_type = reader.format().map(|f| f.to_mime_type());
+    let (width, height) = reader.into_dimensions().ok()?;
+    Some((mime_type, width, height))
+}
+
+// Result of validating an image before it's embedded
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ImageInfo {
+    pub format: String,
+    pub mime_type: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Accept either raw base64 or a full data URL
+fn decode_image_data(image_data: &str) -> Result<Vec<u8>, PlayerError> {
+    let encoded = match image_data.split_once(";base64,") {
+        Some((_, data)) => data,
+        None => image_data,
+    };
+    general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| PlayerError::InvalidInput(format!("Invalid base64 image data: {}", e)))
+}
+
+// Check that image data is a complete, decodable image within MAX_COVER_DIMENSION. The
+// size comes from the header first so an oversized image is rejected before allocating
+// its pixels; the full decode then catches truncated or corrupt data.
+fn validate_image_data(data: &[u8]) -> Result<ImageInfo, PlayerError> {
+    let format = image::guess_format(data)
+        .map_err(|e| PlayerError::UnsupportedFormat(format!("Unrecognised image data: {}", e)))?;
+    let (width, height) = image_info(data)
+        .map(|(_, width, height)| (width, height))
+        .ok_or_else(|| PlayerError::InvalidInput("Could not read image dimensions".to_string()))?;
+
+    let max_dimension = MAX_COVER_DIMENSION.load(Ordering::Relaxed);
+    if width > max_dimension || height > max_dimension {
+        return Err(PlayerError::InvalidInput(format!(
+            "Image is {}x{}, larger than the {}px limit",
+            width, height, max_dimension
+        )));
+    }
+
+    image::load_from_memory_with_format(data, format)
+        .map_err(|e| PlayerError::InvalidInput(format!("Invalid image data: {}", e)))?;
+
+    Ok(ImageInfo {
+        format: format!("{:?}", format).to_lowercase(),
+        mime_type: format.to_mime_type().to_string(),
+        width,
+        height,
+    })
+}
+
+// Set the largest width or height validate_image and set_cover_art accept
+#[tauri::command]
+pub fn set_max_cover_dimension(max_dimension: u32) -> Result<(), PlayerError> {
+    if max_dimension == 0 {
+        return Err(PlayerError::InvalidInput(
+            "Maximum cover dimension must be greater than 0".to_string(),
+        ));
+    }
+    MAX_COVER_DIMENSION.store(max_dimension, Ordering::Relaxed);
+    Ok(())
+}
+
+// Fully decode base64 image data (raw or a data URL) to make sure it isn't truncated or
+// corrupt, returning its real format and size
+#[tauri::command]
+pub async fn validate_image(image_data: String) -> Result<ImageInfo, PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let data = decode_image_data(&image_data)?;
+        validate_image_data(&data)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn get_cover_art(
+    file_path: String,
+    max_dimension: Option<u32>,
+) -> Result<Option<CoverArt>, PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let (mut data, mut mime_type) = match read_embedded_picture(path) {
+            Some(picture) => picture,
+            None => return Ok(None),
+        };
+
+        if let Some(max) = max_dimension.filter(|&m| m > 0) {
+            match downscale_image(&data, max) {
+                Ok(Some(resized)) => {
+                    data = resized;
+                    mime_type = "image/jpeg".to_string();
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    // Fall back to the original artwork rather than failing the request
+                    eprintln!("⚠️ Could not resize cover art for {}: {}", file_path, e);
+                }
+            }
+        }
+
+        // Tags often carry the wrong MIME type, so trust the image header when we can read it
+        let (width, height) = match image_info(&data) {
+            Some((sniffed_mime, width, height)) => {
+                if let Some(sniffed) = sniffed_mime {
+                    mime_type = sniffed.to_string();
+                }
+                (width, height)
+            }
+            None => {
+                eprintln!("⚠️ Could not read cover art dimensions for {}", file_path);
+                (0, 0)
+            }
+        };
+
+        Ok(Some(CoverArt {
+            data: general_purpose::STANDARD.encode(&data),
+            mime_type,
+            width,
+            height,
+        }))
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Write a square JPEG thumbnail of the embedded artwork into the thumbnails cache and
+// return its path, so grids can load art through the asset protocol instead of base64
+#[tauri::command]
+pub async fn get_cover_thumbnail_path(
+    app: AppHandle,
+    file_path: String,
+    size: u32,
+) -> Result<Option<String>, PlayerError> {
+    if size == 0 {
+        return Err(PlayerError::InvalidInput(
+            "Thumbnail size must be greater than zero".to_string(),
+        ));
+    }
+    let thumbnail_dir = app.path().app_data_dir()?.join("thumbnails");
+
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let key = file_key(format!("{}\0{}", file_path, size).as_bytes());
+        let thumbnail_path = thumbnail_dir.join(format!("{}.jpg", key));
+
+        // Reuse the cached thumbnail unless the audio file (and so maybe its art) changed since
+        let source_modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        let thumbnail_modified = fs::metadata(&thumbnail_path).and_then(|m| m.modified()).ok();
+        if let (Some(source), Some(thumbnail)) = (source_modified, thumbnail_modified) {
+            if thumbnail >= source {
+                return Ok(Some(thumbnail_path.to_string_lossy().to_string()));
+            }
+        }
+
+        let Some((data, _)) = read_embedded_picture(path) else {
+            return Ok(None);
+        };
+
+        let img = image::load_from_memory(&data)
+            .map_err(|e| PlayerError::Decode(format!("Failed to decode image: {}", e)))?;
+        // JPEG has no alpha channel, so flatten to RGB before encoding
+        let thumbnail = image::DynamicImage::ImageRgb8(
+            img.resize_to_fill(size, size, image::imageops::FilterType::Lanczos3)
+                .to_rgb8(),
+        );
+
+        if !thumbnail_dir.exists() {
+            fs::create_dir_all(&thumbnail_dir)?;
+        }
+        let mut buffer = Cursor::new(Vec::new());
+        thumbnail
+            .write_to(&mut buffer, image::ImageFormat::Jpeg)
+            .map_err(|e| PlayerError::Io(format!("Failed to encode thumbnail: {}", e)))?;
+        fs::write(&thumbnail_path, buffer.into_inner())?;
+
+        Ok(Some(thumbnail_path.to_string_lossy().to_string()))
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn remove_cover_art(file_path: String, backup: bool) -> Result<(), PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let extension = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        if backup && tag_writer(&extension).is_some() {
+            create_backup(path)?;
+        }
+
+        match tag_writer(&extension) {
+            Some(TagWriter::Id3) => {
+                let mut tag = match id3::Tag::read_from_path(path) {
+                    Ok(tag) => tag,
+                    // No tag means no artwork to remove
+                    Err(id3::Error { kind: id3::ErrorKind::NoTag, .. }) => return Ok(()),
+                    Err(e) => {
+                        return Err(PlayerError::MetadataRead(format!(
+                            "Failed to read ID3 tags: {}",
+                            e
+                        )))
+                    }
+                };
+
+                tag.remove_all_pictures();
+
+                tag.write_to_path(path, id3::Version::Id3v24)
+                    .map_err(|e| {
+                        PlayerError::MetadataWrite(format!("Failed to write ID3 tags: {}", e))
+                    })?;
+
+                Ok(())
+            }
+            Some(TagWriter::Mp4) => {
+                let mut tag = mp4ameta::Tag::read_from_path(path)
+                    .map_err(|e| {
+                        PlayerError::MetadataRead(format!("Failed to read MP4 tags: {}", e))
+                    })?;
+
+                tag.remove_artworks();
+
+                tag.write_to_path(path)
+                    .map_err(|e| {
+                        PlayerError::MetadataWrite(format!("Failed to write MP4 tags: {}", e))
+                    })?;
+
+                Ok(())
+            }
+            Some(TagWriter::Lofty) => {
+                use lofty::{AudioFile, Probe, TaggedFileExt};
+
+                let mut tagged_file = Probe::open(path)
+                    .map_err(|e| PlayerError::MetadataRead(format!("Failed to open file: {}", e)))?
+                    .read()
+                    .map_err(|e| PlayerError::MetadataRead(format!("Failed to read tags: {}", e)))?;
+
+                // Pictures can live in more than one tag (e.g. ID3v2 and RIFF INFO in WAV)
+                let tag_types: Vec<_> = tagged_file.tags().iter().map(|t| t.tag_type()).collect();
+                for tag_type in tag_types {
+                    if let Some(tag) = tagged_file.tag_mut(tag_type) {
+                        while !tag.pictures().is_empty() {
+                            tag.remove_picture(0);
+                        }
+                    }
+                }
+
+                tagged_file
+                    .save_to_path(path)
+                    .map_err(|e| {
+                        PlayerError::MetadataWrite(format!("Failed to save tags: {}", e))
+                    })?;
+
+                Ok(())
+            }
+            None => Err(PlayerError::UnsupportedFormat(format!(
+                "Cover art removal not supported for .{} files",
+                extension
+            ))),
+        }
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+#[tauri::command]
+pub async fn set_cover_art(
+    file_path: String,
+    image_data: String,
+    mime_type: String,
+    backup: bool,
+) -> Result<(), PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+
+        if !path.exists() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let data = decode_image_data(&image_data)?;
+        // Make sure the image actually decodes before embedding it
+        validate_image_data(&data)?;
+
+        let mime_type = mime_type.to_lowercase();
+        let extension = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        if backup && tag_writer(&extension).is_some() {
+            create_backup(path)?;
+        }
+
+        match tag_writer(&extension) {
+            Some(TagWriter::Id3) => {
+                // Don't replace a tag we merely failed to parse with an empty one
+                let mut tag = match id3::Tag::read_from_path(path) {
+                    Ok(tag) => tag,
+                    Err(id3::Error { kind: id3::ErrorKind::NoTag, .. }) => id3::Tag::new(),
+                    Err(e) => {
+                        return Err(PlayerError::MetadataRead(format!(
+                            "Failed to read ID3 tags: {}",
+                            e
+                        )))
+                    }
+                };
+
+                tag.remove_picture_by_type(id3::frame::PictureType::CoverFront);
+                tag.add_frame(id3::frame::Picture {
+                    mime_type,
+                    picture_type: id3::frame::PictureType::CoverFront,
+                    description: String::new(),
+                    data,
+                });
+
+                tag.write_to_path(path, id3::Version::Id3v24)
+                    .map_err(|e| {
+                        PlayerError::MetadataWrite(format!("Failed to write ID3 tags: {}", e))
+                    })?;
+
+                Ok(())
+            }
+            Some(TagWriter::Mp4) => {
+                let fmt = match mime_type.as_str() {
+                    "image/jpeg" | "image/jpg" => mp4ameta::ImgFmt::Jpeg,
+                    "image/png" => mp4ameta::ImgFmt::Png,
+                    "image/bmp" => mp4ameta::ImgFmt::Bmp,
+                    _ => {
+                        return Err(PlayerError::UnsupportedFormat(format!(
+                            "MP4 artwork must be JPEG, PNG or BMP (got {})",
+                            mime_type
+                        )))
+                    }
+                };
+
+                let mut tag = mp4ameta::Tag::read_from_path(path)
+                    .map_err(|e| {
+                        PlayerError::MetadataRead(format!("Failed to read MP4 tags: {}", e))
+                    })?;
+
+                tag.set_artwork(mp4ameta::Img::new(fmt, data));
+
+                tag.write_to_path(path)
+                    .map_err(|e| {
+                        PlayerError::MetadataWrite(format!("Failed to write MP4 tags: {}", e))
+                    })?;
+
+                Ok(())
+            }
+            Some(TagWriter::Lofty) => {
+                use lofty::{Picture, PictureType, Probe, TagExt, TaggedFileExt};
+
+                let mut tagged_file = Probe::open(path)
+                    .map_err(|e| PlayerError::MetadataRead(format!("Failed to open file: {}", e)))?
+                    .read()
+                    .map_err(|e| PlayerError::MetadataRead(format!("Failed to read tags: {}", e)))?;
+
+                let tag = match tagged_file.primary_tag_mut() {
+                    Some(primary_tag) => primary_tag,
+                    None => {
+                        // If no tag, create one based on file type
+                        let tag_type = tagged_file.file_type().primary_tag_type();
+                        tagged_file.insert_tag(lofty::Tag::new(tag_type));
+                        tagged_file.primary_tag_mut().unwrap()
+                    }
+                };
+
+                tag.remove_picture_type(PictureType::CoverFront);
+                tag.push_picture(Picture::new_unchecked(
+                    PictureType::CoverFront,
+                    lofty::MimeType::from_str(&mime_type),
+                    None,
+                    data,
+                ));
+
+                tag.save_to_path(path)
+                    .map_err(|e| {
+                        PlayerError::MetadataWrite(format!("Failed to save tags: {}", e))
+                    })?;
+
+                Ok(())
+            }
+            None => Err(PlayerError::UnsupportedFormat(format!(
+                "Cover art editing not supported for .{} files",
+                extension
+            ))),
+        }
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
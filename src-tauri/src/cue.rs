@@ -0,0 +1,407 @@
+// src-tauri/src/cue.rs
+use crate::error::PlayerError;
+use crate::media_player::{read_track_metadata, Track, SUPPORTED_AUDIO_EXTENSIONS};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// INDEX times are mm:ss:ff with 75 frames per second (CD sectors)
+const CUE_FRAMES_PER_SECOND: f64 = 75.0;
+
+#[derive(Debug, Default)]
+struct CueTrack {
+    number: u32,
+    title: Option<String>,
+    performer: Option<String>,
+    songwriter: Option<String>,
+    // INDEX 01, where the track itself starts
+    start: Option<f64>,
+    // INDEX 00, the start of the pregap before it
+    pregap: Option<f64>,
+}
+
+#[derive(Debug)]
+struct CueFile {
+    name: String,
+    tracks: Vec<CueTrack>,
+}
+
+#[derive(Debug, Default)]
+struct CueSheet {
+    title: Option<String>,
+    performer: Option<String>,
+    genre: Option<String>,
+    year: Option<i32>,
+    files: Vec<CueFile>,
+}
+
+// A command argument, with surrounding quotes removed if it was quoted
+fn unquote(value: &str) -> Option<String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+        .trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+// FILE "name with spaces.flac" WAVE; the file type after the name is ignored
+fn parse_file_name(args: &str) -> Option<String> {
+    let args = args.trim();
+    if let Some(rest) = args.strip_prefix('"') {
+        let end = rest.rfind('"')?;
+        return unquote(&rest[..end]);
+    }
+    match args.rsplit_once(char::is_whitespace) {
+        Some((name, _)) => unquote(name),
+        None => unquote(args),
+    }
+}
+
+fn parse_index_time(value: &str) -> Option<f64> {
+    let mut parts = value.trim().split(':').map(|p| p.parse::<u32>().ok());
+    let minutes = parts.next()??;
+    let seconds = parts.next()??;
+    let frames = parts.next()??;
+    Some(minutes as f64 * 60.0 + seconds as f64 + frames as f64 / CUE_FRAMES_PER_SECOND)
+}
+
+fn parse_cue(contents: &str) -> CueSheet {
+    let mut sheet = CueSheet::default();
+
+    for line in contents.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        let (command, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let command = command.to_uppercase();
+
+        match command.as_str() {
+            "FILE" => {
+                if let Some(name) = parse_file_name(args) {
+                    sheet.files.push(CueFile {
+                        name,
+                        tracks: Vec::new(),
+                    });
+                }
+            }
+            "TRACK" => {
+                // TRACK 01 AUDIO; data tracks on enhanced CDs have nothing to play
+                let mut words = args.split_whitespace();
+                let number = words.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+                let kind = words.next().unwrap_or("AUDIO");
+                if let Some(file) = sheet
+                    .files
+                    .last_mut()
+                    .filter(|_| kind.eq_ignore_ascii_case("AUDIO"))
+                {
+                    file.tracks.push(CueTrack {
+                        number,
+                        ..Default::default()
+                    });
+                }
+            }
+            "INDEX" => {
+                // INDEX 01 is where the track starts; INDEX 00 is the pregap before it
+                let mut words = args.split_whitespace();
+                let index = words.next().and_then(|n| n.parse::<u32>().ok());
+                let time = words.next().and_then(parse_index_time);
+                let track = sheet.files.last_mut().and_then(|f| f.tracks.last_mut());
+                match (index, time, track) {
+                    (Some(0), Some(time), Some(track)) => track.pregap = Some(time),
+                    (Some(1), Some(time), Some(track)) => track.start = Some(time),
+                    _ => {}
+                }
+            }
+            "TITLE" | "PERFORMER" | "SONGWRITER" => {
+                let value = unquote(args);
+                let track = sheet.files.last_mut().and_then(|f| f.tracks.last_mut());
+                match (command.as_str(), track) {
+                    ("TITLE", Some(track)) => track.title = value,
+                    ("PERFORMER", Some(track)) => track.performer = value,
+                    ("SONGWRITER", Some(track)) => track.songwriter = value,
+                    // Before the first TRACK these describe the whole album
+                    ("TITLE", None) => sheet.title = value,
+                    ("PERFORMER", None) => sheet.performer = value,
+                    _ => {}
+                }
+            }
+            "REM" => {
+                let (key, value) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                match key.to_uppercase().as_str() {
+                    "GENRE" => sheet.genre = unquote(value),
+                    "DATE" => {
+                        sheet.year =
+                            unquote(value).and_then(|v| v.get(..4).and_then(|y| y.parse().ok()))
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    sheet
+}
+
+// Cue sheets should be UTF-8, but ones from older CD rippers are usually CP1252 (or its
+// Latin-1 subset). Anything that isn't valid UTF-8 is read as CP1252.
+fn decode_cue(bytes: &[u8]) -> String {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+    bytes.iter().map(|&b| cp1252_char(b)).collect()
+}
+
+fn cp1252_char(byte: u8) -> char {
+    // 0x80-0x9F are control codes in Latin-1 but punctuation and letters in CP1252. The
+    // five bytes CP1252 leaves undefined keep their Latin-1 meaning.
+    const HIGH: [char; 32] = [
+        '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}',
+        '\u{2021}', '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}',
+        '\u{8F}', '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}',
+        '\u{2014}', '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}',
+        '\u{178}',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+// Where each track in a file starts: INDEX 01, or the pregap's INDEX 00 when a sloppy
+// cue leaves it out. The first track may have neither and starts at the top of the file;
+// any other track without an index can't be placed and is skipped.
+fn track_starts(file: &CueFile) -> Vec<(&CueTrack, f64)> {
+    file.tracks
+        .iter()
+        .enumerate()
+        .filter_map(|(i, track)| {
+            let start = track.start.or(track.pregap).or((i == 0).then_some(0.0));
+            if start.is_none() {
+                eprintln!("Cue sheet: skipping track {} with no INDEX", track.number);
+            }
+            Some((track, start?))
+        })
+        .collect()
+}
+
+// Resolve a FILE entry against the cue's directory. Rippers often convert the audio after
+// writing the cue, so "Album.wav" may now be "Album.flac" next to it.
+fn resolve_cue_file(base_dir: &Path, name: &str) -> Option<PathBuf> {
+    let entry = Path::new(name);
+    let resolved = if entry.is_absolute() {
+        entry.to_path_buf()
+    } else {
+        base_dir.join(entry)
+    };
+    if resolved.is_file() {
+        return Some(resolved);
+    }
+
+    SUPPORTED_AUDIO_EXTENSIONS
+        .iter()
+        .map(|ext| resolved.with_extension(ext))
+        .find(|candidate| candidate.is_file())
+}
+
+// Parse a cue sheet into one Track per cue track. Every track points at its audio file
+// with `start_offset` set, so the player seeks to it and stops `duration` seconds later.
+#[tauri::command]
+pub async fn load_cue_sheet(cue_path: String) -> Result<Vec<Track>, PlayerError> {
+    println!("Loading cue sheet: {}", cue_path);
+
+    let result = tauri::async_runtime::spawn(async move {
+        let cue_file = Path::new(&cue_path);
+
+        if !cue_file.exists() {
+            return Err(PlayerError::FileNotFound(cue_path));
+        }
+
+        let bytes = fs::read(cue_file)?;
+        let sheet = parse_cue(&decode_cue(&bytes));
+        let base_dir = cue_file.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut tracks = Vec::new();
+
+        for file in &sheet.files {
+            let Some(audio_path) = resolve_cue_file(base_dir, &file.name) else {
+                eprintln!("Cue sheet: skipping missing file {:?}", file.name);
+                continue;
+            };
+            let audio_path = audio_path.to_string_lossy().to_string();
+
+            // The audio file's own tags fill in whatever the cue doesn't say
+            let file_track = read_track_metadata(audio_path.clone(), false, false);
+
+            let starts = track_starts(file);
+
+            for (i, &(cue_track, start)) in starts.iter().enumerate() {
+                // Each track runs until the next one in the same file, the last to the end
+                let end = starts
+                    .get(i + 1)
+                    .map_or(file_track.duration, |&(_, next)| next);
+
+                let mut track = file_track.clone();
+                track.title = cue_track
+                    .title
+                    .clone()
+                    .or_else(|| Some(format!("Track {:02}", cue_track.number)));
+                track.artist = cue_track
+                    .performer
+                    .clone()
+                    .or_else(|| sheet.performer.clone())
+                    .or(file_track.artist.clone());
+                track.artists = track.artist.clone().map(|artist| vec![artist]);
+                track.album = sheet.title.clone().or(file_track.album.clone());
+                track.album_artist = sheet.performer.clone().or(file_track.album_artist.clone());
+                track.composer = cue_track.songwriter.clone().or(file_track.composer.clone());
+                track.genre = sheet.genre.clone().or(file_track.genre.clone());
+                track.year = sheet.year.or(file_track.year);
+                track.track_number = Some(cue_track.number);
+                track.duration = (end - start).max(0.0);
+                track.start_offset = Some(start);
+                // Gain tags on the file describe the whole rip, not this track
+                track.replaygain_track_gain = None;
+                track.replaygain_track_peak = None;
+                tracks.push(track);
+            }
+        }
+
+        if tracks.is_empty() {
+            return Err(PlayerError::InvalidInput(
+                "No playable tracks found in cue sheet".to_string(),
+            ));
+        }
+
+        println!("Loaded {} tracks from cue sheet", tracks.len());
+        Ok(tracks)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn starts(file: &CueFile) -> Vec<(u32, f64)> {
+        track_starts(file)
+            .into_iter()
+            .map(|(track, start)| (track.number, start))
+            .collect()
+    }
+
+    #[test]
+    fn parses_a_single_file_sheet() {
+        let sheet = parse_cue(
+            "\u{feff}REM GENRE Jazz\n\
+             REM DATE 1959\n\
+             PERFORMER \"Miles Davis\"\n\
+             TITLE \"Kind of Blue\"\n\
+             FILE \"Kind of Blue.flac\" WAVE\n\
+             TRACK 01 AUDIO\n\
+             TITLE \"So What\"\n\
+             INDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\n\
+             TITLE \"Freddie Freeloader\"\n\
+             PERFORMER \"Miles Davis Sextet\"\n\
+             INDEX 01 09:22:37\n",
+        );
+
+        assert_eq!(sheet.title.as_deref(), Some("Kind of Blue"));
+        assert_eq!(sheet.performer.as_deref(), Some("Miles Davis"));
+        assert_eq!(sheet.genre.as_deref(), Some("Jazz"));
+        assert_eq!(sheet.year, Some(1959));
+        assert_eq!(sheet.files.len(), 1);
+
+        let file = &sheet.files[0];
+        assert_eq!(file.name, "Kind of Blue.flac");
+        assert_eq!(file.tracks[0].title.as_deref(), Some("So What"));
+        assert_eq!(
+            file.tracks[1].performer.as_deref(),
+            Some("Miles Davis Sextet")
+        );
+        assert_eq!(
+            starts(file),
+            vec![(1, 0.0), (2, 9.0 * 60.0 + 22.0 + 37.0 / 75.0)]
+        );
+    }
+
+    #[test]
+    fn parses_a_sheet_with_a_file_per_track() {
+        let sheet = parse_cue(
+            "FILE \"01 Intro.wav\" WAVE\n\
+             TRACK 01 AUDIO\n\
+             INDEX 01 00:00:00\n\
+             FILE 02.wav WAVE\n\
+             TRACK 02 AUDIO\n\
+             INDEX 01 00:00:00\n\
+             TRACK 03 MODE1/2352\n\
+             INDEX 01 04:00:00\n",
+        );
+
+        assert_eq!(sheet.files.len(), 2);
+        assert_eq!(sheet.files[0].name, "01 Intro.wav");
+        assert_eq!(sheet.files[1].name, "02.wav");
+        assert_eq!(starts(&sheet.files[0]), vec![(1, 0.0)]);
+        // The data track is dropped
+        assert_eq!(starts(&sheet.files[1]), vec![(2, 0.0)]);
+    }
+
+    #[test]
+    fn pregap_does_not_move_the_track_start() {
+        let sheet = parse_cue(
+            "FILE \"album.flac\" WAVE\n\
+             TRACK 01 AUDIO\n\
+             INDEX 01 00:00:00\n\
+             TRACK 02 AUDIO\n\
+             INDEX 00 03:58:00\n\
+             INDEX 01 04:00:00\n",
+        );
+
+        let track = &sheet.files[0].tracks[1];
+        assert_eq!(track.pregap, Some(3.0 * 60.0 + 58.0));
+        assert_eq!(track.start, Some(4.0 * 60.0));
+        assert_eq!(starts(&sheet.files[0]), vec![(1, 0.0), (2, 240.0)]);
+    }
+
+    #[test]
+    fn tracks_without_index_01() {
+        let sheet = parse_cue(
+            "FILE \"album.flac\" WAVE\n\
+             TRACK 01 AUDIO\n\
+             TRACK 02 AUDIO\n\
+             INDEX 00 03:00:00\n\
+             TRACK 03 AUDIO\n\
+             TITLE \"No index at all\"\n\
+             TRACK 04 AUDIO\n\
+             INDEX 01 07:00:00\n",
+        );
+
+        // The first track starts the file, the second falls back to its pregap and the
+        // third can't be placed
+        assert_eq!(
+            starts(&sheet.files[0]),
+            vec![(1, 0.0), (2, 180.0), (4, 420.0)]
+        );
+    }
+
+    #[test]
+    fn decodes_utf8_and_cp1252() {
+        assert_eq!(
+            decode_cue("TITLE \"Sigur Rós\"".as_bytes()),
+            "TITLE \"Sigur Rós\""
+        );
+        assert_eq!(
+            decode_cue(b"TITLE \"Sigur R\xf3s \x96 \x93Hopp\xedpolla\x94\""),
+            "TITLE \"Sigur Rós – “Hoppípolla”\""
+        );
+    }
+}
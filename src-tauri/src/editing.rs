@@ -0,0 +1,592 @@
+// src-tauri/src/editing.rs
+use crate::analysis::{decode_interleaved, decode_interleaved_from};
+use crate::error::PlayerError;
+use crate::media_player::{copy_metadata, probe_hint, read_track_metadata};
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::sync::mpsc::{sync_channel, Receiver};
+use symphonia::core::codecs::CODEC_TYPE_NULL;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::io::MediaSourceStream;
+use tauri::{AppHandle, Emitter};
+
+// Scale a -1.0..=1.0 sample to a signed integer of the given bit depth
+fn quantize(sample: f32, bits_per_sample: u16) -> i32 {
+    let max = ((1i64 << (bits_per_sample - 1)) - 1) as f32;
+    (sample.clamp(-1.0, 1.0) * max).round() as i32
+}
+
+// Streams integer PCM into a WAV file. The RIFF and data sizes aren't known until the
+// end, so they're written as zero and patched in `finish`.
+pub(crate) struct WavWriter {
+    out: BufWriter<File>,
+    bits_per_sample: u16,
+    data_bytes: u64,
+}
+
+impl WavWriter {
+    pub fn create(
+        path: &Path,
+        sample_rate: u32,
+        channels: u16,
+        bits_per_sample: u16,
+    ) -> Result<Self, PlayerError> {
+        if bits_per_sample != 16 && bits_per_sample != 24 {
+            return Err(PlayerError::InvalidInput(format!(
+                "Unsupported WAV bit depth: {}",
+                bits_per_sample
+            )));
+        }
+
+        let mut out = BufWriter::new(File::create(path)?);
+        let block_align = channels * bits_per_sample / 8;
+        out.write_all(b"RIFF")?;
+        out.write_all(&0u32.to_le_bytes())?;
+        out.write_all(b"WAVEfmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?; // PCM
+        out.write_all(&channels.to_le_bytes())?;
+        out.write_all(&sample_rate.to_le_bytes())?;
+        out.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+        out.write_all(&block_align.to_le_bytes())?;
+        out.write_all(&bits_per_sample.to_le_bytes())?;
+        out.write_all(b"data")?;
+        out.write_all(&0u32.to_le_bytes())?;
+
+        Ok(Self {
+            out,
+            bits_per_sample,
+            data_bytes: 0,
+        })
+    }
+
+    // Interleaved samples in -1.0..=1.0
+    pub fn write_samples(&mut self, samples: &[f32]) -> Result<(), PlayerError> {
+        let bytes_per_sample = self.bits_per_sample as usize / 8;
+        for &sample in samples {
+            let value = quantize(sample, self.bits_per_sample);
+            self.out
+                .write_all(&value.to_le_bytes()[..bytes_per_sample])?;
+        }
+        self.data_bytes += samples.len() as u64 * self.bits_per_sample as u64 / 8;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), PlayerError> {
+        // The data chunk must be word aligned
+        if self.data_bytes % 2 == 1 {
+            self.out.write_all(&[0])?;
+        }
+        let data_bytes = u32::try_from(self.data_bytes)
+            .map_err(|_| PlayerError::InvalidInput("Output exceeds the 4 GB WAV limit".into()))?;
+        let riff_bytes = 36 + data_bytes + data_bytes % 2;
+
+        self.out.seek(SeekFrom::Start(4))?;
+        self.out.write_all(&riff_bytes.to_le_bytes())?;
+        self.out.seek(SeekFrom::Start(40))?;
+        self.out.write_all(&data_bytes.to_le_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+// Bit depth to keep when decoding to WAV: 24-bit for hi-res sources, 16-bit for
+// everything else (lossy codecs don't report one)
+pub(crate) fn output_bit_depth(path: &Path) -> u16 {
+    let bits = File::open(path).ok().and_then(|source| {
+        let mss = MediaSourceStream::new(Box::new(source), Default::default());
+        let hint = probe_hint(path);
+        let probed = symphonia::default::get_probe()
+            .format(&hint, mss, &Default::default(), &Default::default())
+            .ok()?;
+        let track = probed
+            .format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)?;
+        track.codec_params.bits_per_sample
+    });
+    match bits {
+        Some(bits) if bits > 16 => 24,
+        _ => 16,
+    }
+}
+
+// Copy the MPEG frames that start inside the range without decoding them. Cuts land on
+// frame boundaries (about 26 ms), and the source's ID3 tag is carried over.
+fn cut_mp3_frames(
+    input: &Path,
+    output: &Path,
+    start_secs: f64,
+    end_secs: f64,
+) -> Result<(), PlayerError> {
+    let source = File::open(input)?;
+    let mss = MediaSourceStream::new(Box::new(source), Default::default());
+    let hint = probe_hint(input);
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &Default::default(), &Default::default())
+        .map_err(|e| PlayerError::UnsupportedFormat(format!("Failed to probe: {}", e)))?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| PlayerError::UnsupportedFormat("No audio track found".to_string()))?;
+    let track_id = track.id;
+    let time_base = track
+        .codec_params
+        .time_base
+        .ok_or_else(|| PlayerError::UnsupportedFormat("Stream has no time base".to_string()))?;
+
+    let mut out = BufWriter::new(File::create(output)?);
+    let mut frames_written = 0usize;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(PlayerError::Decode(format!("Failed to read packet: {}", e))),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let time = time_base.calc_time(packet.ts());
+        let secs = time.seconds as f64 + time.frac;
+        if secs >= end_secs {
+            break;
+        }
+        if secs >= start_secs {
+            out.write_all(&packet.data)?;
+            frames_written += 1;
+        }
+    }
+    out.flush()?;
+    drop(out);
+
+    if frames_written == 0 {
+        let _ = std::fs::remove_file(output);
+        return Err(PlayerError::InvalidInput(
+            "No audio frames in the requested range".into(),
+        ));
+    }
+
+    // TLEN would describe the whole source, so leave it out of the copy
+    match id3::Tag::read_from_path(input) {
+        Ok(mut tag) => {
+            tag.remove("TLEN");
+            tag.write_to_path(output, id3::Version::Id3v24)
+                .map_err(|e| {
+                    PlayerError::MetadataWrite(format!("Failed to write ID3 tags: {}", e))
+                })?;
+        }
+        Err(id3::Error { kind: id3::ErrorKind::NoTag, .. }) => {}
+        Err(e) => eprintln!("⚠️ Could not copy tags to {:?}: {}", output, e),
+    }
+    Ok(())
+}
+
+// Decode the range and write it out as WAV
+fn cut_to_wav(
+    input: &Path,
+    output: &Path,
+    start_secs: f64,
+    end_secs: f64,
+) -> Result<(), PlayerError> {
+    let bits_per_sample = output_bit_depth(input);
+    let mut writer: Option<WavWriter> = None;
+    let mut write_error: Option<PlayerError> = None;
+    let mut frames_left: Option<u64> = None;
+
+    decode_interleaved_from(input, start_secs, |samples, channels, sample_rate| {
+        if writer.is_none() {
+            match WavWriter::create(output, sample_rate, channels as u16, bits_per_sample) {
+                Ok(w) => writer = Some(w),
+                Err(e) => {
+                    write_error = Some(e);
+                    return ControlFlow::Break(());
+                }
+            }
+        }
+        let remaining = frames_left
+            .get_or_insert_with(|| ((end_secs - start_secs) * sample_rate as f64).round() as u64);
+        let take = (samples.len() / channels).min(*remaining as usize);
+        if let Some(w) = writer.as_mut() {
+            if let Err(e) = w.write_samples(&samples[..take * channels]) {
+                write_error = Some(e);
+                return ControlFlow::Break(());
+            }
+        }
+        *remaining -= take as u64;
+        if *remaining == 0 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    })?;
+
+    if let Some(e) = write_error {
+        return Err(e);
+    }
+    writer
+        .ok_or_else(|| PlayerError::Decode("No audio could be decoded".to_string()))?
+        .finish()
+}
+
+// Write only `start_secs..end_secs` of a file. MP3 to MP3 is cut frame by frame without
+// re-encoding; anything else is decoded and written as WAV.
+#[tauri::command]
+pub async fn trim_audio(
+    input_path: String,
+    output_path: String,
+    start_secs: f64,
+    end_secs: f64,
+) -> Result<(), PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let input = Path::new(&input_path);
+        let output = Path::new(&output_path);
+
+        if !input.exists() {
+            return Err(PlayerError::FileNotFound(input_path));
+        }
+        if input == output {
+            return Err(PlayerError::InvalidInput(
+                "Output must be a different file from the input".to_string(),
+            ));
+        }
+        if !start_secs.is_finite() || !end_secs.is_finite() || start_secs < 0.0 {
+            return Err(PlayerError::InvalidInput(format!(
+                "Invalid range: {} to {}",
+                start_secs, end_secs
+            )));
+        }
+        if start_secs >= end_secs {
+            return Err(PlayerError::InvalidInput(format!(
+                "Start ({}s) must be before end ({}s)",
+                start_secs, end_secs
+            )));
+        }
+
+        let duration = read_track_metadata(input_path.clone(), false, true).duration;
+        if duration > 0.0 && end_secs > duration {
+            return Err(PlayerError::InvalidInput(format!(
+                "End ({}s) is past the end of the file ({:.2}s)",
+                end_secs, duration
+            )));
+        }
+
+        let extension = |path: &Path| {
+            path.extension()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_lowercase()
+        };
+        let input_ext = extension(input);
+        let output_ext = extension(output);
+
+        match (input_ext.as_str(), output_ext.as_str()) {
+            ("mp3", "mp3") => cut_mp3_frames(input, output, start_secs, end_secs)?,
+            (_, "wav") => cut_to_wav(input, output, start_secs, end_secs)?,
+            _ => {
+                return Err(PlayerError::UnsupportedFormat(format!(
+                    "Can't cut .{} to .{} without re-encoding; choose a .wav output",
+                    input_ext, output_ext
+                )))
+            }
+        }
+
+        println!(
+            "✅ Trimmed {} ({}s - {}s) to {}",
+            input_path, start_secs, end_secs, output_path
+        );
+        Ok(())
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Payload of the `transcode-progress` event
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct TranscodeProgress {
+    pub input_path: String,
+    pub progress: f64, // 0.0 - 1.0
+}
+
+// Decoded buffers handed from the decoder thread to the FLAC encoder as it asks for them,
+// so only a few buffers are held at a time instead of the whole track
+struct StreamedSource<P: FnMut(u64, u32)> {
+    buffers: Receiver<(Vec<i32>, usize, u32)>,
+    pending: Vec<i32>,
+    offset: usize,
+    channels: usize,
+    bits_per_sample: usize,
+    sample_rate: usize,
+    frames_read: u64,
+    on_progress: P,
+}
+
+impl<P: FnMut(u64, u32)> flacenc::source::Source for StreamedSource<P> {
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn bits_per_sample(&self) -> usize {
+        self.bits_per_sample
+    }
+
+    fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    fn read_samples<F: flacenc::source::Fill>(
+        &mut self,
+        block_size: usize,
+        dest: &mut F,
+    ) -> Result<usize, flacenc::error::SourceError> {
+        let wanted = block_size * self.channels;
+        let mut block = Vec::with_capacity(wanted);
+        while block.len() < wanted {
+            if self.offset == self.pending.len() {
+                // A closed channel means the decoder is done
+                let Ok((buffer, _, _)) = self.buffers.recv() else {
+                    break;
+                };
+                self.pending = buffer;
+                self.offset = 0;
+                continue;
+            }
+            let take = (wanted - block.len()).min(self.pending.len() - self.offset);
+            block.extend_from_slice(&self.pending[self.offset..self.offset + take]);
+            self.offset += take;
+        }
+        if block.is_empty() {
+            return Ok(0);
+        }
+
+        dest.fill_interleaved(&block)?;
+        let frames = block.len() / self.channels;
+        self.frames_read += frames as u64;
+        (self.on_progress)(self.frames_read, self.sample_rate as u32);
+        Ok(frames)
+    }
+}
+
+// Encode the decoded stream as FLAC. Decoding runs on its own thread and feeds the
+// encoder through a small bounded channel.
+fn encode_flac(
+    input: &Path,
+    output: &Path,
+    bits_per_sample: u16,
+    on_progress: impl FnMut(u64, u32),
+) -> Result<(), PlayerError> {
+    use flacenc::component::BitRepr;
+    use flacenc::error::Verify;
+
+    // Enough for the encoder to never wait on the decoder without buffering much
+    const BUFFERS_IN_FLIGHT: usize = 16;
+
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| PlayerError::InvalidInput(format!("Bad FLAC settings: {:?}", e)))?;
+
+    let (sender, receiver) = sync_channel(BUFFERS_IN_FLIGHT);
+    let (encoded, decoded) = std::thread::scope(|scope| {
+        let decoder = scope.spawn(move || {
+            decode_interleaved(input, |buffer, channels, sample_rate| {
+                let samples = buffer.iter().map(|&s| quantize(s, bits_per_sample));
+                match sender.send((samples.collect(), channels, sample_rate)) {
+                    Ok(()) => ControlFlow::Continue(()),
+                    // The encoder gave up, so there's no point decoding the rest
+                    Err(_) => ControlFlow::Break(()),
+                }
+            })
+        });
+
+        // The first buffer tells the encoder the stream's format
+        let encoded = match receiver.recv() {
+            Ok((first, channels, sample_rate)) => {
+                let source = StreamedSource {
+                    buffers: receiver,
+                    pending: first,
+                    offset: 0,
+                    channels,
+                    bits_per_sample: bits_per_sample as usize,
+                    sample_rate: sample_rate as usize,
+                    frames_read: 0,
+                    on_progress,
+                };
+                // Dropping the source on return also stops the decoder if encoding failed
+                flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+                    .map(Some)
+                    .map_err(|e| PlayerError::Decode(format!("FLAC encoding failed: {:?}", e)))
+            }
+            Err(_) => Ok(None),
+        };
+
+        let decoded = decoder.join().unwrap_or_else(|_| {
+            Err(PlayerError::Task(
+                "FLAC decoder thread panicked".to_string(),
+            ))
+        });
+        (encoded, decoded)
+    });
+    decoded?;
+    let flac_stream =
+        encoded?.ok_or_else(|| PlayerError::Decode("No audio could be decoded".to_string()))?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    flac_stream
+        .write(&mut sink)
+        .map_err(|e| PlayerError::Io(format!("Failed to write FLAC stream: {:?}", e)))?;
+    std::fs::write(output, sink.as_slice())?;
+    Ok(())
+}
+
+// Stream the decoded audio straight into a WAV file
+fn encode_wav(
+    input: &Path,
+    output: &Path,
+    bits_per_sample: u16,
+    mut on_progress: impl FnMut(u64, u32),
+) -> Result<(), PlayerError> {
+    let mut writer: Option<WavWriter> = None;
+    let mut write_error: Option<PlayerError> = None;
+    let mut frames = 0u64;
+
+    decode_interleaved(input, |samples, channels, sample_rate| {
+        if writer.is_none() {
+            match WavWriter::create(output, sample_rate, channels as u16, bits_per_sample) {
+                Ok(w) => writer = Some(w),
+                Err(e) => {
+                    write_error = Some(e);
+                    return ControlFlow::Break(());
+                }
+            }
+        }
+        if let Some(w) = writer.as_mut() {
+            if let Err(e) = w.write_samples(samples) {
+                write_error = Some(e);
+                return ControlFlow::Break(());
+            }
+        }
+        frames += (samples.len() / channels) as u64;
+        on_progress(frames, sample_rate);
+        ControlFlow::Continue(())
+    })?;
+
+    if let Some(e) = write_error {
+        return Err(e);
+    }
+    writer
+        .ok_or_else(|| PlayerError::Decode("No audio could be decoded".to_string()))?
+        .finish()
+}
+
+// Re-encode a file as FLAC or WAV and copy its tags and artwork across. `quality` is
+// the output bit depth (16 or 24); by default hi-res sources stay 24-bit. Progress is
+// reported through `transcode-progress` events.
+#[tauri::command]
+pub async fn transcode(
+    app: AppHandle,
+    input_path: String,
+    output_path: String,
+    target_format: String,
+    quality: Option<u32>,
+) -> Result<(), PlayerError> {
+    let target_format = target_format.trim().trim_start_matches('.').to_lowercase();
+    if target_format != "flac" && target_format != "wav" {
+        return Err(PlayerError::UnsupportedFormat(format!(
+            "Can't encode to {}; supported targets are flac and wav",
+            target_format
+        )));
+    }
+    if let Some(bits) = quality.filter(|&b| b != 16 && b != 24) {
+        return Err(PlayerError::InvalidInput(format!(
+            "Bit depth must be 16 or 24, got {}",
+            bits
+        )));
+    }
+
+    let source_path = input_path.clone();
+    let dest_path = output_path.clone();
+    let result = tauri::async_runtime::spawn(async move {
+        let input = Path::new(&input_path);
+        let output = Path::new(&output_path);
+
+        if !input.exists() {
+            return Err(PlayerError::FileNotFound(input_path));
+        }
+        if input == output {
+            return Err(PlayerError::InvalidInput(
+                "Output must be a different file from the input".to_string(),
+            ));
+        }
+
+        let bits_per_sample = match quality {
+            Some(bits) => bits as u16,
+            None => output_bit_depth(input),
+        };
+
+        // Report roughly every percent rather than once per packet
+        let duration = read_track_metadata(input_path.clone(), false, true).duration;
+        let mut last_reported = -1.0;
+        let on_progress = |frames: u64, sample_rate: u32| {
+            if duration <= 0.0 || sample_rate == 0 {
+                return;
+            }
+            let progress = (frames as f64 / sample_rate as f64 / duration).min(1.0);
+            if progress - last_reported >= 0.01 {
+                last_reported = progress;
+                let _ = app.emit(
+                    "transcode-progress",
+                    TranscodeProgress {
+                        input_path: input_path.clone(),
+                        progress,
+                    },
+                );
+            }
+        };
+
+        let encoded = if target_format == "flac" {
+            encode_flac(input, output, bits_per_sample, on_progress)
+        } else {
+            encode_wav(input, output, bits_per_sample, on_progress)
+        };
+        if let Err(e) = encoded {
+            // Don't leave a half-written file behind
+            let _ = std::fs::remove_file(output);
+            return Err(e);
+        }
+
+        let _ = app.emit(
+            "transcode-progress",
+            TranscodeProgress {
+                input_path: input_path.clone(),
+                progress: 1.0,
+            },
+        );
+        Ok(())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(e),
+        Err(e) => return Err(PlayerError::Task(e.to_string())),
+    }
+
+    // The audio is done at this point, so a tag the target can't hold only costs a warning
+    if let Err(e) = copy_metadata(source_path.clone(), dest_path.clone(), true).await {
+        eprintln!("⚠️ Transcoded {} but couldn't copy its tags: {}", source_path, e);
+    }
+    println!("✅ Transcoded {} to {}", source_path, dest_path);
+    Ok(())
+}
@@ -0,0 +1,61 @@
+// src-tauri/src/error.rs
+use std::fmt;
+
+// Error returned by every command. Serialized as { "kind": "...", "message": "..." }
+// so the frontend can branch on `kind` instead of matching message strings.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum PlayerError {
+    FileNotFound(String),
+    PlaylistNotFound(String),
+    AlreadyExists(String),
+    InvalidInput(String),
+    UnsupportedFormat(String),
+    MetadataRead(String),
+    MetadataWrite(String),
+    Decode(String),
+    Io(String),
+    Parse(String),
+    Task(String),
+}
+
+impl fmt::Display for PlayerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayerError::FileNotFound(msg) => write!(f, "File not found: {}", msg),
+            PlayerError::PlaylistNotFound(msg) => write!(f, "Playlist not found: {}", msg),
+            PlayerError::AlreadyExists(msg) => write!(f, "Already exists: {}", msg),
+            PlayerError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
+            PlayerError::UnsupportedFormat(msg) => write!(f, "Unsupported format: {}", msg),
+            PlayerError::MetadataRead(msg) => write!(f, "Failed to read metadata: {}", msg),
+            PlayerError::MetadataWrite(msg) => write!(f, "Failed to write metadata: {}", msg),
+            PlayerError::Decode(msg) => write!(f, "Failed to decode audio: {}", msg),
+            PlayerError::Io(msg) => write!(f, "I/O error: {}", msg),
+            PlayerError::Parse(msg) => write!(f, "Parse error: {}", msg),
+            PlayerError::Task(msg) => write!(f, "Task failed to execute: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for PlayerError {}
+
+impl From<std::io::Error> for PlayerError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => PlayerError::FileNotFound(e.to_string()),
+            _ => PlayerError::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for PlayerError {
+    fn from(e: serde_json::Error) -> Self {
+        PlayerError::Parse(e.to_string())
+    }
+}
+
+impl From<tauri::Error> for PlayerError {
+    fn from(e: tauri::Error) -> Self {
+        PlayerError::Io(e.to_string())
+    }
+}
@@ -9,8 +9,19 @@ fn test_command() -> String {
     "Test works!".to_string()
 }
 
+pub mod analysis;
+pub mod cache;
+pub mod chapters;
+pub mod cover_art;
+pub mod cue;
+pub mod editing;
+pub mod error;
+pub mod library;
 pub mod media_player;
+pub mod playback_state;
 pub mod playlist;
+pub mod smart_playlist;
+pub mod stream;
 
 use tauri::Manager;
 
@@ -25,7 +36,17 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
+        .manage(playlist::PendingPlaylistSaves::default())
         .setup(|app| {
+            match cache::MetadataCache::new(app.handle()) {
+                Ok(metadata_cache) => {
+                    app.manage(metadata_cache);
+                }
+                Err(e) => {
+                    println!("❌ Metadata cache unavailable: {}", e);
+                }
+            }
+
             #[cfg(target_os = "windows")]
             {
                 use std::sync::Mutex;
@@ -78,17 +99,100 @@ pub fn run() {
             greet,
             test_command,
             get_audio_metadata,
+            media_player::get_audio_metadata_batch,
             media_player::get_audio_file_info,
             update_metadata,
+            media_player::copy_metadata,
+            media_player::set_rating,
+            media_player::write_replaygain,
+            media_player::get_capabilities,
+            media_player::restore_backup,
+            media_player::set_compilation,
+            media_player::set_bpm,
+            media_player::set_read_retry_attempts,
+            media_player::repair_tags,
             check_file_exists,
+            media_player::detect_format,
+            media_player::scan_folder,
+            media_player::scan_folder_with_progress,
+            media_player::get_lyrics,
+            media_player::get_encoding_info,
+            chapters::get_chapters,
+            chapters::get_markers,
+            cue::load_cue_sheet,
+            media_player::get_all_tags,
+            cover_art::get_cover_art,
+            cover_art::get_cover_thumbnail_path,
+            cache::clear_metadata_cache,
+            cache::clear_waveform_cache,
+            stream::get_stream_metadata,
+            analysis::generate_waveform,
+            analysis::decode_preview,
+            analysis::compute_audio_hash,
+            analysis::analyze_peak,
+            analysis::measure_loudness,
+            analysis::compute_album_replaygain,
+            analysis::generate_spectrogram,
+            analysis::recompute_duration,
+            analysis::get_rms_envelope,
+            analysis::detect_bpm,
+            analysis::detect_key,
+            editing::trim_audio,
+            editing::transcode,
+            analysis::detect_silence_boundaries,
+            analysis::compute_crossfade,
+            library::organize_files,
+            library::rename_file_from_metadata,
+            library::transliterate_filename,
+            library::set_file_date_from_metadata,
+            library::group_album_sequences,
+            playback_state::save_playback_state,
+            playback_state::load_playback_state,
+            playback_state::generate_shuffle_order,
+            cover_art::remove_cover_art,
+            cover_art::set_cover_art,
+            cover_art::validate_image,
+            cover_art::set_max_cover_dimension,
             playlist::save_playlist,
+            playlist::save_playlist_debounced,
             playlist::load_playlist,
             playlist::get_playlists,
+            playlist::get_recent_playlists,
+            playlist::generate_playlist_cover,
+            playlist::externalize_playlist_cover,
             playlist::delete_playlist,
             playlist::rename_playlist,
             playlist::import_folder_as_playlist,
             playlist::update_playlist_tags,
+            playlist::move_playlist_to_folder,
+            playlist::export_playlist_m3u,
+            playlist::export_playlist_bundle,
+            playlist::import_playlist_m3u,
+            playlist::export_all_playlists,
+            playlist::import_all_playlists,
+            playlist::prune_playlist,
+            playlist::validate_playlist,
+            playlist::search_tracks,
+            playlist::library_stats,
+            playlist::find_duplicate_tracks,
+            playlist::find_library_duplicates,
+            playlist::repair_playlist,
+            playlist::merge_playlists,
+            playlist::diff_playlists,
+            playlist::reorder_playlist,
+            playlist::sort_playlist,
+            playlist::add_track_to_playlist,
+            playlist::remove_track_from_playlist,
+            smart_playlist::evaluate_smart_playlist,
+            smart_playlist::save_smart_playlist,
+            smart_playlist::load_smart_playlist,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Debounced playlist saves still waiting on their timer would be lost otherwise
+            if let tauri::RunEvent::Exit = event {
+                playlist::flush_pending_saves(app);
+            }
+        });
 }
\ No newline at end of file
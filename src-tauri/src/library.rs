@@ -0,0 +1,456 @@
+// src-tauri/src/library.rs
+use crate::error::PlayerError;
+use crate::media_player::{read_track_metadata, Track};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Make a metadata value safe to use as a single path component
+fn sanitize_path_component(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    // Windows refuses names ending in a dot or space
+    let trimmed = cleaned.trim().trim_end_matches(['.', ' ']);
+    if trimmed.is_empty() || trimmed == "." || trimmed == ".." {
+        "_".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+// Value for a single `{placeholder}` in the organize pattern
+fn placeholder_value(name: &str, track: &Track, path: &Path) -> Option<String> {
+    let non_empty = |s: &Option<String>| s.as_ref().filter(|s| !s.trim().is_empty()).cloned();
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Unknown")
+        .to_string();
+
+    let value = match name {
+        "artist" => non_empty(&track.artist).unwrap_or_else(|| "Unknown Artist".to_string()),
+        "album_artist" => non_empty(&track.album_artist)
+            .or_else(|| non_empty(&track.artist))
+            .unwrap_or_else(|| "Unknown Artist".to_string()),
+        "album" => non_empty(&track.album).unwrap_or_else(|| "Unknown Album".to_string()),
+        "title" => non_empty(&track.title).unwrap_or(stem),
+        "track_number" => track
+            .track_number
+            .map(|n| format!("{:02}", n))
+            .unwrap_or_else(|| "00".to_string()),
+        "disc_number" => track.disc_number.unwrap_or(1).to_string(),
+        "year" => track.year.map(|y| y.to_string()).unwrap_or_default(),
+        "genre" => non_empty(&track.genre).unwrap_or_else(|| "Unknown Genre".to_string()),
+        "ext" => path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase(),
+        "filename" => stem,
+        _ => return None,
+    };
+    Some(value)
+}
+
+// Expand the `{placeholder}`s in one path component of a pattern, sanitizing each value
+fn expand_pattern_segment(
+    segment: &str,
+    pattern: &str,
+    track: &Track,
+    path: &Path,
+) -> Result<String, PlayerError> {
+    let mut component = String::new();
+    let mut rest = segment;
+
+    while let Some(start) = rest.find('{') {
+        component.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            return Err(PlayerError::InvalidInput(format!(
+                "Unclosed placeholder in pattern: {}",
+                pattern
+            )));
+        };
+        let name = &rest[start + 1..start + len];
+        let value = placeholder_value(name, track, path).ok_or_else(|| {
+            PlayerError::InvalidInput(format!("Unknown placeholder: {{{}}}", name))
+        })?;
+        component.push_str(&sanitize_path_component(&value));
+        rest = &rest[start + len + 1..];
+    }
+    component.push_str(rest);
+
+    if component == "." || component == ".." {
+        return Err(PlayerError::InvalidInput(format!(
+            "Pattern must not contain relative path segments: {}",
+            pattern
+        )));
+    }
+    Ok(component)
+}
+
+// Expand a pattern like `{album_artist}/{album}/{track_number} - {title}.{ext}`
+// into a relative path, sanitizing each substituted value
+fn build_relative_path(pattern: &str, track: &Track, path: &Path) -> Result<PathBuf, PlayerError> {
+    let mut relative = PathBuf::new();
+
+    for segment in pattern.split(['/', '\\']).filter(|s| !s.is_empty()) {
+        relative.push(expand_pattern_segment(segment, pattern, track, path)?);
+    }
+
+    if relative.as_os_str().is_empty() {
+        return Err(PlayerError::InvalidInput("Pattern is empty".to_string()));
+    }
+    Ok(relative)
+}
+
+// Append " (2)", " (3)", ... to the file stem until the path is free
+pub(crate) fn unique_destination(destination: PathBuf) -> PathBuf {
+    if !destination.exists() {
+        return destination;
+    }
+
+    let stem = destination
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_string();
+    let extension = destination.extension().and_then(|e| e.to_str()).map(|e| e.to_string());
+
+    let mut n = 2;
+    loop {
+        let name = match &extension {
+            Some(ext) => format!("{} ({}).{}", stem, n, ext),
+            None => format!("{} ({})", stem, n),
+        };
+        let candidate = destination.with_file_name(name);
+        if !candidate.exists() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+// Rename, falling back to copy + delete when moving across filesystems
+fn move_file(from: &Path, to: &Path) -> Result<(), PlayerError> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn organize_files(
+    file_paths: Vec<String>,
+    destination_root: String,
+    pattern: String,
+) -> Result<Vec<String>, PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let root = Path::new(&destination_root);
+        if root.exists() && !root.is_dir() {
+            return Err(PlayerError::InvalidInput(format!(
+                "Destination is not a directory: {}",
+                destination_root
+            )));
+        }
+
+        let mut new_paths = Vec::with_capacity(file_paths.len());
+
+        for file_path in file_paths {
+            let path = Path::new(&file_path);
+            if !path.is_file() {
+                return Err(PlayerError::FileNotFound(file_path));
+            }
+
+            let track = read_track_metadata(file_path.clone(), false, true);
+            let relative = build_relative_path(&pattern, &track, path)?;
+            let destination = root.join(relative);
+
+            // Already where it belongs
+            if destination == path {
+                new_paths.push(file_path);
+                continue;
+            }
+
+            if let Some(parent) = destination.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let destination = unique_destination(destination);
+            move_file(path, &destination)?;
+
+            eprintln!("Moved {} -> {:?}", file_path, destination);
+            new_paths.push(destination.to_string_lossy().to_string());
+        }
+
+        Ok(new_paths)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Rename a file in place from its metadata, e.g. `{artist} - {title}`. The original
+// extension is always kept, so the pattern only describes the file stem.
+#[tauri::command]
+pub async fn rename_file_from_metadata(
+    file_path: String,
+    pattern: String,
+) -> Result<String, PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+        if !path.is_file() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+        if pattern.contains(['/', '\\']) {
+            return Err(PlayerError::InvalidInput(format!(
+                "Rename pattern must not contain path separators: {}",
+                pattern
+            )));
+        }
+
+        let track = read_track_metadata(file_path.clone(), false, true);
+        let stem = expand_pattern_segment(&pattern, &pattern, &track, path)?;
+        if stem.trim().is_empty() {
+            return Err(PlayerError::InvalidInput("Pattern is empty".to_string()));
+        }
+        let new_name = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) => format!("{}.{}", stem, ext),
+            None => stem,
+        };
+
+        let destination = path.with_file_name(new_name);
+        if destination == path {
+            return Ok(file_path);
+        }
+        let destination = unique_destination(destination);
+        fs::rename(path, &destination)?;
+
+        eprintln!("Renamed {} -> {:?}", file_path, destination);
+        Ok(destination.to_string_lossy().to_string())
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Rename a file so its name is plain ASCII ("Beyoncé - Déjà Vu.mp3" becomes
+// "Beyonce - Deja Vu.mp3"), for FAT32 sticks and car stereos that mangle anything else.
+// The extension is kept as is, and a clash with an existing file gets a " (2)" suffix.
+#[tauri::command]
+pub async fn transliterate_filename(file_path: String) -> Result<String, PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+        if !path.is_file() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if stem.is_ascii() {
+            return Ok(file_path);
+        }
+
+        // Characters deunicode has no approximation for come back as "[?]"
+        let ascii_stem = sanitize_path_component(&deunicode::deunicode(&stem));
+        let new_name = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) => format!("{}.{}", ascii_stem, ext),
+            None => ascii_stem,
+        };
+
+        let destination = unique_destination(path.with_file_name(new_name));
+        fs::rename(path, &destination)?;
+
+        eprintln!("Transliterated {} -> {:?}", file_path, destination);
+        Ok(destination.to_string_lossy().to_string())
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Parse "2019", "2019-04" or "2019-04-12T10:00:00" into (year, month, day); missing
+// parts default to January 1
+fn parse_release_date(s: &str) -> Option<(i32, u32, u32)> {
+    let mut parts = s.trim().split(['-', '/', 'T', ' ']);
+    let year: i32 = parts.next()?.trim().parse().ok()?;
+    if !(1..=9999).contains(&year) {
+        return None;
+    }
+    let month = parts
+        .next()
+        .and_then(|m| m.trim().parse().ok())
+        .filter(|m| (1..=12).contains(m));
+    let day = month
+        .and(parts.next())
+        .and_then(|d| d.trim().parse().ok())
+        .filter(|d| (1..=31).contains(d));
+    Some((year, month.unwrap_or(1), day.unwrap_or(1)))
+}
+
+// The most precise release date the tags have, falling back to the plain year
+fn read_release_date(path: &Path, file_path: &str) -> Option<(i32, u32, u32)> {
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let date = match extension.as_str() {
+        "mp3" | "aiff" | "aif" => {
+            use id3::TagLike;
+            id3::Tag::read_from_path(path).ok().and_then(|tag| {
+                tag.date_released()
+                    .or_else(|| tag.date_recorded())
+                    .map(|ts| {
+                        let month = ts.month.map_or(1, u32::from);
+                        (ts.year, month, ts.day.map_or(1, u32::from))
+                    })
+            })
+        }
+        "m4a" | "mp4" | "aac" => mp4ameta::Tag::read_from_path(path)
+            .ok()
+            .and_then(|tag| tag.year().and_then(parse_release_date)),
+        _ => {
+            use lofty::{ItemKey, Probe, TaggedFileExt};
+            Probe::open(path)
+                .and_then(|p| p.read())
+                .ok()
+                .and_then(|tagged_file| {
+                    tagged_file.tags().iter().find_map(|tag| {
+                        tag.get_string(&ItemKey::RecordingDate)
+                            .or_else(|| tag.get_string(&ItemKey::Year))
+                            .and_then(parse_release_date)
+                    })
+                })
+        }
+    };
+
+    date.or_else(|| {
+        read_track_metadata(file_path.to_string(), false, true)
+            .year
+            .map(|year| (year, 1, 1))
+    })
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's days_from_civil)
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year } as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Set the file's modification time to its release date (midnight UTC) so file managers
+// sort albums chronologically
+#[tauri::command]
+pub async fn set_file_date_from_metadata(file_path: String) -> Result<(), PlayerError> {
+    let result = tauri::async_runtime::spawn(async move {
+        let path = Path::new(&file_path);
+        if !path.is_file() {
+            return Err(PlayerError::FileNotFound(file_path));
+        }
+
+        let Some((year, month, day)) = read_release_date(path, &file_path) else {
+            return Err(PlayerError::MetadataRead(format!(
+                "No release date tag found in {}",
+                file_path
+            )));
+        };
+
+        let seconds = days_from_civil(year, month, day) * 86_400;
+        filetime::set_file_mtime(path, filetime::FileTime::from_unix_time(seconds, 0))?;
+
+        eprintln!("Set {} modified time to {}-{:02}-{:02}", file_path, year, month, day);
+        Ok(())
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Album key used to decide whether two tracks come from the same release
+fn album_key(track: &Track) -> Option<(String, String)> {
+    let album = track.album.as_deref()?.trim().to_lowercase();
+    if album.is_empty() {
+        return None;
+    }
+    let album_artist = track
+        .album_artist
+        .as_deref()
+        .or(track.artist.as_deref())
+        .unwrap_or("")
+        .trim()
+        .to_lowercase();
+    Some((album, album_artist))
+}
+
+fn continues_sequence(prev: &Track, next: &Track) -> bool {
+    let (Some(prev_number), Some(next_number)) = (prev.track_number, next.track_number) else {
+        return false;
+    };
+    let prev_key = album_key(prev);
+    if prev_key.is_none() || prev_key != album_key(next) {
+        return false;
+    }
+
+    // Tracks without a disc number are treated as disc 1
+    let prev_disc = prev.disc_number.unwrap_or(1);
+    let next_disc = next.disc_number.unwrap_or(1);
+    if next_disc == prev_disc {
+        next_number == prev_number + 1
+    } else {
+        // The last track of one disc flows into the first track of the next
+        next_disc == prev_disc + 1
+            && next_number == 1
+            && prev.total_tracks.is_none_or(|total| prev_number == total)
+    }
+}
+
+// Group runs of adjacent tracks that share an album and have consecutive track numbers,
+// so gapless transitions are only used inside real album sequences. Each group holds at
+// least two indices; tracks that don't continue a run are left out.
+#[tauri::command]
+pub fn group_album_sequences(tracks: Vec<Track>) -> Result<Vec<Vec<usize>>, PlayerError> {
+    let mut groups = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+
+    for (i, track) in tracks.iter().enumerate() {
+        let continues = i > 0 && continues_sequence(&tracks[i - 1], track);
+        if !continues {
+            if current.len() > 1 {
+                groups.push(std::mem::take(&mut current));
+            }
+            current.clear();
+        }
+        current.push(i);
+    }
+    if current.len() > 1 {
+        groups.push(current);
+    }
+
+    Ok(groups)
+}
@@ -0,0 +1,146 @@
+// src-tauri/src/playback_state.rs
+use crate::error::PlayerError;
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri::Manager;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RepeatMode {
+    #[default]
+    None,
+    All,
+    One,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct PlaybackState {
+    pub playlist_name: Option<String>,
+    pub track_index: usize,
+    pub position: f64, // seconds
+    pub volume: f64,
+    #[serde(default)]
+    pub shuffle: bool,
+    #[serde(default)]
+    pub repeat: RepeatMode,
+    // Seed returned by generate_shuffle_order, so a restored session keeps its shuffle.
+    // A string because JavaScript numbers can't hold every u64.
+    #[serde(default, deserialize_with = "deserialize_seed")]
+    pub shuffle_seed: Option<String>,
+}
+
+// Older state files stored the seed as a JSON number
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum StoredSeed {
+    Text(String),
+    Number(u64),
+}
+
+fn deserialize_seed<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+
+    Ok(
+        Option::<StoredSeed>::deserialize(deserializer)?.map(|seed| match seed {
+            StoredSeed::Text(text) => text,
+            StoredSeed::Number(number) => number.to_string(),
+        }),
+    )
+}
+
+fn get_state_path(app: &AppHandle) -> Result<PathBuf, PlayerError> {
+    let app_data_dir = app.path().app_data_dir()?;
+
+    if !app_data_dir.exists() {
+        fs::create_dir_all(&app_data_dir)?;
+    }
+
+    Ok(app_data_dir.join("playback_state.json"))
+}
+
+#[tauri::command]
+pub fn save_playback_state(app: AppHandle, state: PlaybackState) -> Result<(), PlayerError> {
+    let file_path = get_state_path(&app)?;
+    let json = serde_json::to_string_pretty(&state)?;
+    fs::write(file_path, json)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn load_playback_state(app: AppHandle) -> Result<Option<PlaybackState>, PlayerError> {
+    let file_path = get_state_path(&app)?;
+
+    if !file_path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(file_path)?;
+    let state: PlaybackState = serde_json::from_str(&json)?;
+    Ok(Some(state))
+}
+
+// SplitMix64: tiny, well distributed and stable across platforms and releases,
+// which is what makes a stored seed reproduce the same order
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform value in 0..bound, rejecting the biased tail
+    fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - (u64::MAX % bound);
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+// A shuffled order and the seed that produced it, as a decimal string
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ShuffleOrder {
+    pub order: Vec<usize>,
+    pub seed: String,
+}
+
+// Fisher-Yates permutation of 0..track_count. The same seed always yields the same
+// order; without one a seed is taken from the clock. The seed used is returned so the
+// frontend can keep it in the playback state.
+#[tauri::command]
+pub fn generate_shuffle_order(
+    track_count: usize,
+    seed: Option<String>,
+) -> Result<ShuffleOrder, PlayerError> {
+    let seed = match seed {
+        Some(text) => text.trim().parse::<u64>().map_err(|_| {
+            PlayerError::InvalidInput(format!("Shuffle seed must be a u64, got {}", text))
+        })?,
+        None => std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0),
+    };
+    let mut rng = SplitMix64(seed);
+
+    let mut order: Vec<usize> = (0..track_count).collect();
+    for i in (1..order.len()).rev() {
+        let j = rng.below(i as u64 + 1) as usize;
+        order.swap(i, j);
+    }
+    Ok(ShuffleOrder {
+        order,
+        seed: seed.to_string(),
+    })
+}
@@ -1,8 +1,26 @@
-use crate::media_player::{get_audio_metadata, Track};
+use crate::analysis::{audio_fingerprint, probes_successfully};
+use crate::cache::{file_key, FingerprintCache};
+use crate::error::PlayerError;
+use crate::library::unique_destination;
+use crate::media_player::{get_audio_metadata, get_audio_metadata_batch, Track};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
 use tauri::AppHandle;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    #[default]
+    Manual,
+    ByTitle,
+    ByArtist,
+    ByAlbum,
+    ByDuration,
+}
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct Playlist {
@@ -13,171 +31,1864 @@ pub struct Playlist {
     pub tags: Vec<String>,
     #[serde(default)]
     pub folder_path: Option<String>,
+    // Sidebar folder the playlist is grouped under, e.g. "Workout" or "Genres/Jazz"
+    #[serde(default)]
+    pub folder: Option<String>,
+    // Kept when tracks are added; anything but Manual re-sorts the new track into place
+    #[serde(default)]
+    pub sort_mode: SortMode,
+}
+
+fn get_playlist_dir(app: &AppHandle) -> Result<PathBuf, PlayerError> {
+    let app_data_dir = app.path().app_data_dir()?;
+    let playlist_dir = app_data_dir.join("playlists");
+
+    if !playlist_dir.exists() {
+        fs::create_dir_all(&playlist_dir)?;
+    }
+
+    Ok(playlist_dir)
+}
+
+// Playlist names become file names, so make sure they can't point outside the playlists dir
+pub(crate) fn validate_playlist_name(name: &str) -> Result<(), PlayerError> {
+    if name.trim().is_empty() {
+        return Err(PlayerError::InvalidInput(
+            "Playlist name cannot be empty".to_string(),
+        ));
+    }
+    // "Best of..." is fine; only a name that is itself a relative path component isn't
+    if name.contains('/')
+        || name.contains('\\')
+        || name.contains('\0')
+        || matches!(name, "." | "..")
+    {
+        return Err(PlayerError::InvalidInput(format!(
+            "Invalid playlist name '{}': names cannot be '.' or '..', or contain path separators or null bytes",
+            name.replace('\0', "")
+        )));
+    }
+    Ok(())
+}
+
+// Every named read or write goes through here, so a debounced save still waiting on its
+// timer is written first and can't later clobber (or be hidden from) this access
+fn get_playlist_path(app: &AppHandle, name: &str) -> Result<PathBuf, PlayerError> {
+    validate_playlist_name(name)?;
+    flush_pending_save(app, name);
+    let dir = get_playlist_dir(app)?;
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+// Playlists are stored with forward slashes so app data synced between Windows and
+// macOS/Linux keeps working. Only Windows converts on save: on Unix a backslash is a
+// legal file name character, not a separator.
+fn to_stored_path(path: &str) -> String {
+    if cfg!(windows) {
+        path.replace('\\', "/")
+    } else {
+        path.to_string()
+    }
+}
+
+// Back to the current platform's separators. A Unix path with backslashes but no slashes
+// was saved on Windows before paths were normalized. Stream URLs aren't paths at all.
+fn from_stored_path(path: &str) -> String {
+    if path.contains("://") {
+        path.to_string()
+    } else if cfg!(windows) {
+        path.replace('/', "\\")
+    } else if path.contains('\\') && !path.contains('/') {
+        path.replace('\\', "/")
+    } else {
+        path.to_string()
+    }
+}
+
+// Apply a path conversion to every file path a playlist holds. Covers can also be data
+// URLs, which are left alone.
+fn map_playlist_paths(playlist: &mut Playlist, convert: fn(&str) -> String) {
+    let convert_cover = |cover: &mut Option<String>| {
+        if let Some(c) = cover.as_mut().filter(|c| !c.starts_with("data:")) {
+            *c = convert(c);
+        }
+    };
+
+    for track in playlist.tracks.iter_mut() {
+        track.path = convert(&track.path);
+        convert_cover(&mut track.cover_image);
+    }
+    convert_cover(&mut playlist.cover_image);
+    if let Some(folder_path) = playlist.folder_path.as_mut() {
+        *folder_path = convert(folder_path);
+    }
+}
+
+// Covers moved out of the JSON by externalize_playlist_cover, and those made by
+// generate_playlist_cover, live in this subdirectory of the playlists dir. The JSON
+// refers to them relative to it ("covers/1a2b.jpg"), so the app data dir can move
+// without breaking them.
+const COVERS_DIR: &str = "covers";
+
+// Turn a stored "covers/..." reference into a full path next to the playlist file
+fn resolve_cover_reference(playlist_file: &Path, cover: &mut Option<String>) {
+    let Some(dir) = playlist_file.parent() else {
+        return;
+    };
+    if let Some(c) = cover.as_mut() {
+        if let Some(file_name) = c.strip_prefix(&format!("{}/", COVERS_DIR)) {
+            let resolved = dir.join(COVERS_DIR).join(file_name);
+            *c = resolved.to_string_lossy().to_string();
+        }
+    }
+}
+
+// The reverse: a full path inside the covers dir goes back to its relative reference
+fn to_cover_reference(playlist_file: &Path, cover: &mut Option<String>) {
+    let Some(covers_dir) = playlist_file.parent().map(|dir| dir.join(COVERS_DIR)) else {
+        return;
+    };
+    if let Some(c) = cover.as_mut() {
+        let path = Path::new(c.as_str());
+        if path.parent() == Some(covers_dir.as_path()) {
+            if let Some(file_name) = path.file_name() {
+                *c = format!("{}/{}", COVERS_DIR, file_name.to_string_lossy());
+            }
+        }
+    }
+}
+
+// Store cover image bytes under COVERS_DIR, named by content so playlists sharing a
+// cover (e.g. after a merge) share the file
+fn write_cover_file(
+    playlist_dir: &Path,
+    data: &[u8],
+    extension: &str,
+) -> Result<PathBuf, PlayerError> {
+    let covers_dir = playlist_dir.join(COVERS_DIR);
+    fs::create_dir_all(&covers_dir)?;
+    let cover_path = covers_dir.join(format!("{}.{}", file_key(data), extension));
+    if !cover_path.exists() {
+        fs::write(&cover_path, data)?;
+    }
+    Ok(cover_path)
+}
+
+// Write to a temp file next to the target and rename it over the original,
+// so a crash mid-write never leaves a truncated file behind
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), PlayerError> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+    {
+        use std::io::Write;
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+
+    if let Err(e) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+fn save_playlist_to_disk(path: &PathBuf, playlist: &Playlist) -> Result<(), PlayerError> {
+    let mut stored = playlist.clone();
+    to_cover_reference(path, &mut stored.cover_image);
+    map_playlist_paths(&mut stored, to_stored_path);
+    let json = serde_json::to_string_pretty(&stored)?;
+    write_atomically(path, json.as_bytes())
+}
+
+fn load_playlist_from_disk(path: &PathBuf) -> Result<Playlist, PlayerError> {
+    let json = fs::read_to_string(path)?;
+    let mut playlist: Playlist = serde_json::from_str(&json)?;
+    // Resolved first: "covers/..." is always stored with a forward slash, and converting
+    // it to Windows separators would hide it from resolve_cover_reference
+    resolve_cover_reference(path, &mut playlist.cover_image);
+    map_playlist_paths(&mut playlist, from_stored_path);
+    Ok(playlist)
+}
+
+// Every saved playlist file, sorted so results are stable between calls
+fn playlist_files(app: &AppHandle) -> Result<Vec<PathBuf>, PlayerError> {
+    flush_pending_saves(app);
+    let dir = get_playlist_dir(app)?;
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+#[tauri::command]
+pub fn save_playlist(
+    app: AppHandle,
+    name: String,
+    tracks: Vec<Track>,
+    cover_image: Option<String>,
+    tags: Option<Vec<String>>,
+    folder_path: Option<String>,
+) -> Result<(), PlayerError> {
+    println!("Saving playlist: {}, tags: {:?}, folder: {:?}", name, tags, folder_path);
+    let file_path = get_playlist_path(&app, &name)?;
+
+    // The editor doesn't know about folders or sorting, so keep what the playlist already had
+    let (folder, sort_mode) = if file_path.exists() {
+        load_playlist_from_disk(&file_path)
+            .map(|p| (p.folder, p.sort_mode))
+            .unwrap_or_default()
+    } else {
+        (None, SortMode::Manual)
+    };
+
+    let playlist = Playlist {
+        name,
+        tracks,
+        cover_image,
+        tags: tags.unwrap_or_default(),
+        folder_path,
+        folder,
+        sort_mode,
+    };
+
+    save_playlist_to_disk(&file_path, &playlist)?;
+    Ok(())
+}
+
+// How long a debounced save waits for further edits before writing
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+struct PendingSave {
+    generation: u64,
+    tracks: Vec<Track>,
+    cover_image: Option<String>,
+}
+
+// Latest unsaved edit per playlist for save_playlist_debounced. Every call bumps the
+// generation, and a timer only writes if its generation is still the newest.
+#[derive(Default)]
+pub struct PendingPlaylistSaves {
+    pending: Mutex<(u64, HashMap<String, PendingSave>)>,
+}
+
+// Like save_playlist, but coalesces rapid edits (e.g. dragging tracks around) so only
+// the last change within SAVE_DEBOUNCE hits the disk. Tags and folder are kept from
+// the saved playlist. Write failures are reported through `playlist-save-error`.
+#[tauri::command]
+pub async fn save_playlist_debounced(
+    app: AppHandle,
+    state: tauri::State<'_, PendingPlaylistSaves>,
+    name: String,
+    tracks: Vec<Track>,
+    cover_image: Option<String>,
+) -> Result<(), PlayerError> {
+    validate_playlist_name(&name)?;
+
+    let generation = {
+        let mut guard = state
+            .pending
+            .lock()
+            .map_err(|e| PlayerError::Task(e.to_string()))?;
+        let (counter, pending) = &mut *guard;
+        *counter += 1;
+        pending.insert(
+            name.clone(),
+            PendingSave {
+                generation: *counter,
+                tracks,
+                cover_image,
+            },
+        );
+        *counter
+    };
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_DEBOUNCE).await;
+
+        let save = {
+            let state = app.state::<PendingPlaylistSaves>();
+            let Ok(mut guard) = state.pending.lock() else {
+                return;
+            };
+            let (_, pending) = &mut *guard;
+            // A newer edit has its own timer running; let that one write
+            if pending.get(&name).is_none_or(|p| p.generation != generation) {
+                return;
+            }
+            pending.remove(&name)
+        };
+
+        if let Some(save) = save {
+            write_pending_saves(&app, vec![(name, save)]);
+        }
+    });
+
+    Ok(())
+}
+
+fn take_pending_saves(app: &AppHandle, name: Option<&str>) -> Vec<(String, PendingSave)> {
+    let Some(state) = app.try_state::<PendingPlaylistSaves>() else {
+        return Vec::new();
+    };
+    let Ok(mut guard) = state.pending.lock() else {
+        return Vec::new();
+    };
+    let (_, pending) = &mut *guard;
+    match name {
+        Some(name) => pending.remove_entry(name).into_iter().collect(),
+        None => pending.drain().collect(),
+    }
+}
+
+fn write_pending_saves(app: &AppHandle, saves: Vec<(String, PendingSave)>) {
+    for (name, save) in saves {
+        if let Err(e) = write_debounced_save(app, &name, save) {
+            eprintln!("Debounced save of playlist {} failed: {}", name, e);
+            let _ = app.emit("playlist-save-error", (name, e.to_string()));
+        }
+    }
+}
+
+// Writes the playlist's pending debounced save now. Its timer then finds nothing to do.
+pub(crate) fn flush_pending_save(app: &AppHandle, name: &str) {
+    write_pending_saves(app, take_pending_saves(app, Some(name)));
+}
+
+// Writes every pending debounced save now, e.g. before listing playlists or on exit
+pub(crate) fn flush_pending_saves(app: &AppHandle) {
+    write_pending_saves(app, take_pending_saves(app, None));
+}
+
+fn write_debounced_save(app: &AppHandle, name: &str, save: PendingSave) -> Result<(), PlayerError> {
+    let file_path = get_playlist_path(app, name)?;
+    let existing = if file_path.exists() {
+        Some(load_playlist_from_disk(&file_path)?)
+    } else {
+        None
+    };
+
+    save_playlist(
+        app.clone(),
+        name.to_string(),
+        save.tracks,
+        save.cover_image,
+        existing.as_ref().map(|p| p.tags.clone()),
+        existing.and_then(|p| p.folder_path),
+    )
+}
+
+#[tauri::command]
+pub fn load_playlist(app: AppHandle, name: String) -> Result<Playlist, PlayerError> {
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    load_playlist_from_disk(&file_path)
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct PlaylistSummary {
+    pub name: String,
+    pub track_count: usize,
+    pub cover_image: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub folder_path: Option<String>,
+    // Sidebar folder the playlist is grouped under, e.g. "Workout" or "Genres/Jazz"
+    #[serde(default)]
+    pub folder: Option<String>,
+    // Seconds
+    #[serde(default)]
+    pub total_duration: f64,
+    // Only filled in when sizes are requested, since it means stat-ing every file
+    #[serde(default)]
+    pub total_size_bytes: u64,
+    // The file exists but couldn't be parsed; see repair_playlist
+    #[serde(default)]
+    pub corrupt: bool,
+}
+
+fn total_duration(tracks: &[Track]) -> f64 {
+    tracks.iter().map(|t| t.duration).sum()
+}
+
+// Missing files simply don't count towards the total
+fn total_size_bytes(tracks: &[Track]) -> u64 {
+    tracks
+        .iter()
+        .filter_map(|t| fs::metadata(&t.path).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+// Keep a copy of a playlist that failed to parse next to it, so a later save under the
+// same name can't destroy whatever repair_playlist might still recover
+fn quarantine_corrupt_playlist(path: &Path) {
+    let quarantine = path.with_extension("json.corrupt");
+    if quarantine.exists() {
+        return;
+    }
+    if let Err(e) = fs::copy(path, &quarantine) {
+        eprintln!("⚠️ Failed to quarantine {:?}: {}", path, e);
+    }
+}
+
+// Summary of one playlist file, or None if it can't be read at all
+fn playlist_summary(path: &Path, include_sizes: bool) -> Option<PlaylistSummary> {
+    let playlist = match load_playlist_from_disk(path) {
+        Ok(playlist) => playlist,
+        // Still list it, so it doesn't silently vanish from the sidebar
+        Err(PlayerError::Parse(e)) => {
+            eprintln!("⚠️ Corrupt playlist {:?}: {}", path, e);
+            quarantine_corrupt_playlist(path);
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            return Some(PlaylistSummary {
+                name,
+                track_count: 0,
+                total_duration: 0.0,
+                total_size_bytes: 0,
+                cover_image: None,
+                tags: Vec::new(),
+                folder_path: None,
+                folder: None,
+                corrupt: true,
+            });
+        }
+        Err(e) => {
+            eprintln!("⚠️ Skipping unreadable playlist {:?}: {}", path, e);
+            return None;
+        }
+    };
+
+    let total_size_bytes = if include_sizes {
+        total_size_bytes(&playlist.tracks)
+    } else {
+        0
+    };
+    Some(PlaylistSummary {
+        name: playlist.name,
+        track_count: playlist.tracks.len(),
+        total_duration: total_duration(&playlist.tracks),
+        total_size_bytes,
+        cover_image: playlist.cover_image,
+        tags: playlist.tags,
+        folder_path: playlist.folder_path,
+        folder: playlist.folder,
+        corrupt: false,
+    })
+}
+
+#[tauri::command]
+pub fn get_playlists(
+    app: AppHandle,
+    include_sizes: bool,
+) -> Result<Vec<PlaylistSummary>, PlayerError> {
+    Ok(playlist_files(&app)?
+        .iter()
+        .filter_map(|path| playlist_summary(path, include_sizes))
+        .collect())
+}
+
+// The `limit` most recently saved playlists, newest first. Only file times are read up
+// front; the JSON is parsed just for the playlists that make the cut.
+#[tauri::command]
+pub fn get_recent_playlists(
+    app: AppHandle,
+    limit: usize,
+) -> Result<Vec<PlaylistSummary>, PlayerError> {
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = playlist_files(&app)?
+        .into_iter()
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((modified, path))
+        })
+        .collect();
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+
+    // Unreadable files are skipped, so keep going past them until the limit is filled
+    Ok(files
+        .iter()
+        .filter_map(|(_, path)| playlist_summary(path, false))
+        .take(limit)
+        .collect())
+}
+
+// Pull every complete track object out of a "tracks" array, stopping at the first one
+// that's damaged or cut off. Works on truncated files that aren't valid JSON as a whole.
+fn salvage_tracks(json: &str) -> Vec<Track> {
+    let Some(key) = json.find("\"tracks\"") else {
+        return Vec::new();
+    };
+    let Some(open) = json[key..].find('[') else {
+        return Vec::new();
+    };
+
+    let mut tracks = Vec::new();
+    let mut rest = &json[key + open + 1..];
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() || rest.starts_with(']') {
+            break;
+        }
+        let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<serde_json::Value>();
+        let Some(Ok(value)) = stream.next() else {
+            break;
+        };
+        let consumed = stream.byte_offset();
+        // A well-formed object that isn't a valid track is skipped, not fatal
+        match serde_json::from_value::<Track>(value) {
+            Ok(track) => tracks.push(track),
+            Err(e) => eprintln!("⚠️ Dropping unreadable track entry: {}", e),
+        }
+        rest = &rest[consumed..];
+    }
+    tracks
+}
+
+// Rebuild a corrupt playlist from whatever can still be read: the playlist fields if the
+// JSON parses at all, and every intact track. Returns how many tracks were recovered.
+#[tauri::command]
+pub fn repair_playlist(app: AppHandle, name: String) -> Result<usize, PlayerError> {
+    let file_path = get_playlist_path(&app, &name)?;
+    let quarantine = file_path.with_extension("json.corrupt");
+
+    // Prefer the untouched quarantine copy over a file that may have been rewritten since
+    let source = if quarantine.exists() {
+        &quarantine
+    } else if file_path.exists() {
+        &file_path
+    } else {
+        return Err(PlayerError::PlaylistNotFound(name));
+    };
+    let json = String::from_utf8_lossy(&fs::read(source)?).to_string();
+
+    // Each field on its own, so one bad value doesn't cost the rest
+    fn field<T: serde::de::DeserializeOwned>(
+        value: &Option<serde_json::Value>,
+        key: &str,
+    ) -> Option<T> {
+        let field = value.as_ref()?.get(key)?.clone();
+        serde_json::from_value(field).ok()
+    }
+    let value: Option<serde_json::Value> = serde_json::from_str(&json).ok();
+
+    let entries: Option<Vec<serde_json::Value>> = field(&value, "tracks");
+    let tracks = match entries {
+        Some(entries) => entries
+            .into_iter()
+            .filter_map(|entry| serde_json::from_value::<Track>(entry).ok())
+            .collect(),
+        None => salvage_tracks(&json),
+    };
+
+    let mut playlist = Playlist {
+        name: name.clone(),
+        tracks,
+        cover_image: field(&value, "cover_image"),
+        tags: field(&value, "tags").unwrap_or_default(),
+        folder_path: field(&value, "folder_path"),
+        folder: field(&value, "folder"),
+        sort_mode: field(&value, "sort_mode").unwrap_or_default(),
+    };
+    map_playlist_paths(&mut playlist, from_stored_path);
+    save_playlist_to_disk(&file_path, &playlist)?;
+
+    // The repaired file is the playlist now; drop the quarantine copy
+    if quarantine.exists() {
+        fs::remove_file(&quarantine)?;
+    }
+
+    println!(
+        "Repaired playlist {}: recovered {} tracks",
+        name,
+        playlist.tracks.len()
+    );
+    Ok(playlist.tracks.len())
+}
+
+#[tauri::command]
+pub fn delete_playlist(app: AppHandle, name: String) -> Result<(), PlayerError> {
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if file_path.exists() {
+        fs::remove_file(file_path)?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn rename_playlist(
+    app: AppHandle,
+    old_name: String,
+    new_name: String,
+) -> Result<(), PlayerError> {
+    println!("Renaming playlist from {} to {}", old_name, new_name);
+    let old_path = get_playlist_path(&app, &old_name)?;
+    let new_path = get_playlist_path(&app, &new_name)?;
+    rename_playlist_file(&old_path, &new_path, old_name, new_name)
+}
+
+// Move a playlist file to its new name, keeping the original until the copy is verified
+fn rename_playlist_file(
+    old_path: &PathBuf,
+    new_path: &PathBuf,
+    old_name: String,
+    new_name: String,
+) -> Result<(), PlayerError> {
+    if !old_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(old_name));
+    }
+    // Renaming to the same name must not go through write-new/delete-old, which would
+    // delete the only copy
+    if old_name == new_name {
+        return Ok(());
+    }
+
+    let mut playlist = load_playlist_from_disk(old_path)?;
+    playlist.name = new_name.clone();
+
+    // On case-insensitive filesystems "Chill" -> "chill" resolves to the same file
+    let same_file = new_path.exists()
+        && fs::canonicalize(old_path).ok() == fs::canonicalize(new_path).ok();
+    if new_path.exists() && !same_file {
+        return Err(PlayerError::AlreadyExists(format!(
+            "Playlist with new name already exists: {}",
+            new_name
+        )));
+    }
+    if same_file {
+        save_playlist_to_disk(old_path, &playlist)?;
+        fs::rename(old_path, new_path)?;
+        return Ok(());
+    }
+
+    // Write the new file and make sure it reads back before touching the original
+    save_playlist_to_disk(new_path, &playlist)?;
+    match load_playlist_from_disk(new_path) {
+        Ok(written)
+            if written.name == new_name && written.tracks.len() == playlist.tracks.len() => {}
+        Ok(_) => {
+            let _ = fs::remove_file(new_path);
+            return Err(PlayerError::Io(format!(
+                "Renamed playlist did not match the original: {}",
+                new_name
+            )));
+        }
+        Err(e) => {
+            let _ = fs::remove_file(new_path);
+            return Err(e);
+        }
+    }
+
+    // If the original can't be removed, drop the copy so we don't end up with two playlists
+    if let Err(e) = fs::remove_file(old_path) {
+        if old_path.exists() {
+            let _ = fs::remove_file(new_path);
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn update_playlist_tags(
+    app: AppHandle,
+    name: String,
+    tags: Vec<String>,
+) -> Result<(), PlayerError> {
+    println!("Updating tags for playlist: {} to {:?}", name, tags);
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    let mut playlist = load_playlist_from_disk(&file_path)?;
+    playlist.tags = tags;
+
+    save_playlist_to_disk(&file_path, &playlist)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn move_playlist_to_folder(
+    app: AppHandle,
+    name: String,
+    folder: Option<String>,
+) -> Result<(), PlayerError> {
+    println!("Moving playlist {} to folder {:?}", name, folder);
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    // Normalize "Genres / Jazz/" to "Genres/Jazz"; an empty folder means top level
+    let folder = folder
+        .map(|f| {
+            f.split('/')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .filter(|f| !f.is_empty());
+
+    let mut playlist = load_playlist_from_disk(&file_path)?;
+    playlist.folder = folder;
+
+    save_playlist_to_disk(&file_path, &playlist)?;
+    Ok(())
+}
+
+// Move an inline base64 cover out of the playlist JSON into the covers dir, leaving a
+// reference behind. load_playlist and get_playlists resolve it to the file's path. Covers
+// that are already files are left alone.
+#[tauri::command]
+pub fn externalize_playlist_cover(app: AppHandle, name: String) -> Result<(), PlayerError> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    let mut playlist = load_playlist_from_disk(&file_path)?;
+    let Some((header, encoded)) = playlist
+        .cover_image
+        .as_deref()
+        .filter(|c| c.starts_with("data:"))
+        .and_then(|c| c.split_once(";base64,"))
+    else {
+        return Ok(());
+    };
+
+    let data = general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| PlayerError::Parse(format!("Invalid base64 cover image: {}", e)))?;
+    let extension = match header.trim_start_matches("data:") {
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/bmp" => "bmp",
+        _ => "jpg",
+    };
+
+    let cover_path = write_cover_file(&get_playlist_dir(&app)?, &data, extension)?;
+
+    println!("Moved cover of playlist {} to {:?}", name, cover_path);
+    playlist.cover_image = Some(cover_path.to_string_lossy().to_string());
+    save_playlist_to_disk(&file_path, &playlist)?;
+    Ok(())
+}
+
+// Side length of generated playlist covers; each tile of the 2x2 grid is half of it
+const PLAYLIST_COVER_SIZE: u32 = 600;
+
+// Build a cover from the playlist's own artwork: a 2x2 grid of the first four albums that
+// have embedded art, or just the first one when there are fewer. The JPEG is stored in the
+// app data dir and its path saved as the playlist's cover_image.
+#[tauri::command]
+pub async fn generate_playlist_cover(app: AppHandle, name: String) -> Result<String, PlayerError> {
+    use crate::cover_art::read_embedded_picture;
+    use image::imageops::FilterType;
+
+    println!("Generating cover for playlist: {}", name);
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+    let playlist_dir = get_playlist_dir(&app)?;
+
+    let result = tauri::async_runtime::spawn(async move {
+        let mut playlist = load_playlist_from_disk(&file_path)?;
+
+        // One picture per album: tracks from the same release would just repeat the art.
+        // Tracks without an album tag each count as their own.
+        let mut seen_albums = HashSet::new();
+        let mut covers = Vec::new();
+        for track in &playlist.tracks {
+            let artist = track.album_artist.as_deref().or(track.artist.as_deref());
+            let album_key = match &track.album {
+                Some(album) => format!(
+                    "{}\0{}",
+                    normalize_for_compare(artist.unwrap_or("")),
+                    normalize_for_compare(album)
+                ),
+                None => track.path.clone(),
+            };
+            if seen_albums.contains(&album_key) {
+                continue;
+            }
+            let Some((data, _)) = read_embedded_picture(Path::new(&track.path)) else {
+                continue;
+            };
+            match image::load_from_memory(&data) {
+                Ok(img) => {
+                    seen_albums.insert(album_key);
+                    covers.push(img);
+                }
+                Err(e) => eprintln!("⚠️ Skipping unreadable cover in {}: {}", track.path, e),
+            }
+            if covers.len() == 4 {
+                break;
+            }
+        }
+
+        let cover = if covers.len() == 4 {
+            let tile = PLAYLIST_COVER_SIZE / 2;
+            let mut grid = image::RgbImage::new(PLAYLIST_COVER_SIZE, PLAYLIST_COVER_SIZE);
+            for (i, img) in covers.iter().enumerate() {
+                let tile_image = img
+                    .resize_to_fill(tile, tile, FilterType::Lanczos3)
+                    .to_rgb8();
+                let (x, y) = ((i as u32 % 2) * tile, (i as u32 / 2) * tile);
+                image::imageops::replace(&mut grid, &tile_image, x as i64, y as i64);
+            }
+            grid
+        } else if let Some(img) = covers.first() {
+            let size = PLAYLIST_COVER_SIZE;
+            img.resize_to_fill(size, size, FilterType::Lanczos3)
+                .to_rgb8()
+        } else {
+            return Err(PlayerError::InvalidInput(format!(
+                "No cover art found in the tracks of playlist {}",
+                playlist.name
+            )));
+        };
+
+        let mut jpeg = std::io::Cursor::new(Vec::new());
+        image::DynamicImage::ImageRgb8(cover)
+            .write_to(&mut jpeg, image::ImageFormat::Jpeg)
+            .map_err(|e| PlayerError::Io(format!("Failed to encode playlist cover: {}", e)))?;
+        let cover_path = write_cover_file(&playlist_dir, jpeg.get_ref(), "jpg")?;
+
+        let cover_image = cover_path.to_string_lossy().to_string();
+        playlist.cover_image = Some(cover_image.clone());
+        save_playlist_to_disk(&file_path, &playlist)?;
+        Ok(cover_image)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+#[tauri::command]
+pub fn prune_playlist(app: AppHandle, name: String) -> Result<usize, PlayerError> {
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    let mut playlist = load_playlist_from_disk(&file_path)?;
+    let original_count = playlist.tracks.len();
+    playlist.tracks.retain(|t| Path::new(&t.path).exists());
+    let removed = original_count - playlist.tracks.len();
+
+    if removed > 0 {
+        save_playlist_to_disk(&file_path, &playlist)?;
+    }
+
+    println!("Pruned {} missing tracks from playlist: {}", removed, name);
+    Ok(removed)
+}
+
+// Playability of a single playlist entry, as reported by validate_playlist
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct TrackStatus {
+    pub index: usize,
+    pub path: String,
+    pub exists: bool,
+    pub playable: bool,
+}
+
+// Report which tracks are missing or no longer decodable, without touching the playlist
+#[tauri::command]
+pub async fn validate_playlist(
+    app: AppHandle,
+    name: String,
+) -> Result<Vec<TrackStatus>, PlayerError> {
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    let playlist = load_playlist_from_disk(&file_path)?;
+
+    let result = tauri::async_runtime::spawn(async move {
+        playlist
+            .tracks
+            .into_iter()
+            .enumerate()
+            .map(|(index, track)| {
+                let path = Path::new(&track.path);
+                let exists = path.is_file();
+                let playable = exists && probes_successfully(path);
+                TrackStatus {
+                    index,
+                    path: track.path,
+                    exists,
+                    playable,
+                }
+            })
+            .collect::<Vec<_>>()
+    })
+    .await;
+
+    match result {
+        Ok(statuses) => {
+            let broken = statuses.iter().filter(|s| !s.playable).count();
+            println!("Validated playlist {}: {} broken tracks", name, broken);
+            Ok(statuses)
+        }
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+#[tauri::command]
+pub fn reorder_playlist(
+    app: AppHandle,
+    name: String,
+    from_index: usize,
+    to_index: usize,
+) -> Result<(), PlayerError> {
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    let mut playlist = load_playlist_from_disk(&file_path)?;
+    let track_count = playlist.tracks.len();
+
+    if from_index >= track_count || to_index >= track_count {
+        return Err(PlayerError::InvalidInput(format!(
+            "Cannot move track {} to {}: playlist has {} tracks",
+            from_index, to_index, track_count
+        )));
+    }
+
+    if from_index != to_index {
+        let track = playlist.tracks.remove(from_index);
+        playlist.tracks.insert(to_index, track);
+        // Dragging a track means the user wants their own order from now on
+        playlist.sort_mode = SortMode::Manual;
+        save_playlist_to_disk(&file_path, &playlist)?;
+    }
+
+    Ok(())
+}
+
+// Case-insensitive text key; tracks missing the value sort last
+fn text_sort_key(value: &Option<String>) -> (bool, String) {
+    match value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => (false, v.to_lowercase()),
+        None => (true, String::new()),
+    }
+}
+
+fn title_sort_key(track: &Track) -> (bool, String) {
+    match text_sort_key(&track.title) {
+        (true, _) => (false, track.filename.to_lowercase()),
+        key => key,
+    }
+}
+
+// Position within a release: disc, then track number
+fn album_position(track: &Track) -> (u32, u32) {
+    (track.disc_number.unwrap_or(1), track.track_number.unwrap_or(u32::MAX))
+}
+
+// Stable sort, so tracks that compare equal keep their current relative order
+fn sort_tracks(tracks: &mut [Track], mode: SortMode) {
+    match mode {
+        SortMode::Manual => {}
+        SortMode::ByTitle => tracks.sort_by_cached_key(title_sort_key),
+        SortMode::ByArtist => tracks.sort_by_cached_key(|t| {
+            (
+                text_sort_key(&t.artist),
+                text_sort_key(&t.album),
+                album_position(t),
+                title_sort_key(t),
+            )
+        }),
+        SortMode::ByAlbum => tracks.sort_by_cached_key(|t| {
+            (text_sort_key(&t.album), album_position(t), title_sort_key(t))
+        }),
+        SortMode::ByDuration => tracks.sort_by(|a, b| a.duration.total_cmp(&b.duration)),
+    }
+}
+
+#[tauri::command]
+pub fn sort_playlist(
+    app: AppHandle,
+    name: String,
+    mode: SortMode,
+) -> Result<Playlist, PlayerError> {
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    let mut playlist = load_playlist_from_disk(&file_path)?;
+    sort_tracks(&mut playlist.tracks, mode);
+    playlist.sort_mode = mode;
+
+    save_playlist_to_disk(&file_path, &playlist)?;
+    println!("Sorted playlist {} ({:?})", name, mode);
+    Ok(playlist)
+}
+
+#[tauri::command]
+pub fn add_track_to_playlist(app: AppHandle, name: String, track: Track) -> Result<(), PlayerError> {
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    let mut playlist = load_playlist_from_disk(&file_path)?;
+    playlist.tracks.push(track);
+    sort_tracks(&mut playlist.tracks, playlist.sort_mode);
+
+    save_playlist_to_disk(&file_path, &playlist)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn remove_track_from_playlist(
+    app: AppHandle,
+    name: String,
+    index: usize,
+) -> Result<(), PlayerError> {
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    let mut playlist = load_playlist_from_disk(&file_path)?;
+
+    if index >= playlist.tracks.len() {
+        return Err(PlayerError::InvalidInput(format!(
+            "Track index {} is out of range: playlist has {} tracks",
+            index,
+            playlist.tracks.len()
+        )));
+    }
+
+    playlist.tracks.remove(index);
+
+    save_playlist_to_disk(&file_path, &playlist)?;
+    Ok(())
 }
 
-fn get_playlist_dir(app: &AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
-    let playlist_dir = app_data_dir.join("playlists");
+// Search every saved playlist for tracks whose artist, title or album contains the query.
+// With `deduplicate`, a file that appears in several playlists is only returned once.
+#[tauri::command]
+pub fn search_tracks(
+    app: AppHandle,
+    query: String,
+    deduplicate: Option<bool>,
+) -> Result<Vec<(String, Track)>, PlayerError> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        return Ok(Vec::new());
+    }
 
-    if !playlist_dir.exists() {
-        fs::create_dir_all(&playlist_dir).map_err(|e| e.to_string())?;
+    let playlist_paths = playlist_files(&app)?;
+    let deduplicate = deduplicate.unwrap_or(false);
+    let mut seen_paths = HashSet::new();
+    let mut results = Vec::new();
+
+    for path in playlist_paths {
+        let playlist = match load_playlist_from_disk(&path) {
+            Ok(playlist) => playlist,
+            Err(e) => {
+                eprintln!("⚠️ Skipping unreadable playlist {:?}: {}", path, e);
+                continue;
+            }
+        };
+
+        for track in playlist.tracks {
+            let matches = [&track.artist, &track.title, &track.album]
+                .iter()
+                .any(|field| field.as_ref().is_some_and(|v| v.to_lowercase().contains(&needle)));
+            if !matches {
+                continue;
+            }
+            if deduplicate && !seen_paths.insert(track.path.clone()) {
+                continue;
+            }
+            results.push((playlist.name.clone(), track));
+        }
     }
 
-    Ok(playlist_dir)
+    println!("Search for '{}' found {} tracks", query, results.len());
+    Ok(results)
 }
 
-fn get_playlist_path(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
-    let dir = get_playlist_dir(app)?;
-    Ok(dir.join(format!("{}.json", name)))
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct LibraryStats {
+    pub playlist_count: usize,
+    pub unique_tracks: usize,
+    // Seconds
+    pub total_duration: f64,
+    // (extension, track count), most common first
+    pub formats: Vec<(String, usize)>,
+    // (artist, track count), most tracks first
+    pub top_artists: Vec<(String, usize)>,
 }
 
-fn save_playlist_to_disk(path: &PathBuf, playlist: &Playlist) -> Result<(), String> {
-    let json = serde_json::to_string_pretty(playlist).map_err(|e| e.to_string())?;
-    fs::write(path, json).map_err(|e| e.to_string())?;
-    Ok(())
+// Sort (name, count) pairs by count, then name, so ties come out in a stable order
+fn sort_counts(counts: impl IntoIterator<Item = (String, usize)>) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
 }
 
+// Totals across every saved playlist. A file that appears in several playlists is
+// only counted once.
 #[tauri::command]
-pub fn save_playlist(
-    app: AppHandle,
-    name: String,
-    tracks: Vec<Track>,
-    cover_image: Option<String>,
-    tags: Option<Vec<String>>,
-    folder_path: Option<String>,
-) -> Result<(), String> {
-    println!("Saving playlist: {}, tags: {:?}, folder: {:?}", name, tags, folder_path);
-    let file_path = get_playlist_path(&app, &name)?;
+pub fn library_stats(app: AppHandle) -> Result<LibraryStats, PlayerError> {
+    const TOP_ARTISTS: usize = 10;
 
-    let playlist = Playlist {
-        name,
-        tracks,
-        cover_image,
-        tags: tags.unwrap_or_default(),
-        folder_path,
+    let mut playlist_count = 0;
+    let mut seen_paths = HashSet::new();
+    let mut total_duration = 0.0;
+    let mut formats: HashMap<String, usize> = HashMap::new();
+    // Keyed case-insensitively, keeping the first spelling seen for display
+    let mut artists: HashMap<String, (String, usize)> = HashMap::new();
+
+    for path in playlist_files(&app)? {
+        let playlist = match load_playlist_from_disk(&path) {
+            Ok(playlist) => playlist,
+            Err(e) => {
+                eprintln!("⚠️ Skipping unreadable playlist {:?}: {}", path, e);
+                continue;
+            }
+        };
+        playlist_count += 1;
+
+        for track in playlist.tracks {
+            if !seen_paths.insert(normalize_path_for_compare(&track.path)) {
+                continue;
+            }
+            total_duration += track.duration;
+
+            let format = Path::new(&track.path)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+                .unwrap_or_else(|| "unknown".to_string());
+            *formats.entry(format).or_default() += 1;
+
+            if let Some(artist) = track.artist.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+                artists
+                    .entry(normalize_for_compare(artist))
+                    .or_insert_with(|| (artist.to_string(), 0))
+                    .1 += 1;
+            }
+        }
+    }
+
+    let mut top_artists = sort_counts(artists.into_values());
+    top_artists.truncate(TOP_ARTISTS);
+
+    let stats = LibraryStats {
+        playlist_count,
+        unique_tracks: seen_paths.len(),
+        total_duration,
+        formats: sort_counts(formats),
+        top_artists,
     };
+    println!(
+        "Library stats: {} unique tracks across {} playlists",
+        stats.unique_tracks, stats.playlist_count
+    );
+    Ok(stats)
+}
 
-    save_playlist_to_disk(&file_path, &playlist)?;
-    Ok(())
+// Group files referenced by any playlist that decode to the same audio, even when their
+// tags or filenames differ. Fingerprints are cached, so repeat runs only decode new or
+// changed files.
+#[tauri::command]
+pub async fn find_library_duplicates(app: AppHandle) -> Result<Vec<Vec<String>>, PlayerError> {
+    use std::sync::Arc;
+    use tokio::sync::Semaphore;
+
+    let mut seen_paths = HashSet::new();
+    let mut paths = Vec::new();
+    for path in playlist_files(&app)? {
+        let playlist = match load_playlist_from_disk(&path) {
+            Ok(playlist) => playlist,
+            Err(e) => {
+                eprintln!("⚠️ Skipping unreadable playlist {:?}: {}", path, e);
+                continue;
+            }
+        };
+        for track in playlist.tracks {
+            if seen_paths.insert(normalize_path_for_compare(&track.path))
+                && Path::new(&track.path).exists()
+            {
+                paths.push(track.path);
+            }
+        }
+    }
+
+    let mut cache = FingerprintCache::load(&app)?;
+    let mut fingerprints: Vec<(String, String)> = Vec::new();
+    let mut missing = Vec::new();
+    for path in paths {
+        match cache.get(&path) {
+            Some(fingerprint) => fingerprints.push((path, fingerprint)),
+            None => missing.push(path),
+        }
+    }
+    println!(
+        "Fingerprinting {} tracks ({} cached)",
+        missing.len(),
+        fingerprints.len()
+    );
+
+    // Decoding is CPU bound, so keep it to one file per core
+    let max_concurrency = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let semaphore = Arc::new(Semaphore::new(max_concurrency));
+    let tasks: Vec<_> = missing
+        .into_iter()
+        .map(|path| {
+            let sem: Arc<Semaphore> = Arc::clone(&semaphore);
+            tauri::async_runtime::spawn(async move {
+                let _permit = sem
+                    .acquire()
+                    .await
+                    .map_err(|e| PlayerError::Task(e.to_string()))?;
+                // Decode on the blocking pool so it doesn't stall the async workers
+                let decode_path = path.clone();
+                let fingerprint = tauri::async_runtime::spawn_blocking(move || {
+                    audio_fingerprint(Path::new(&decode_path))
+                })
+                .await
+                .map_err(|e| PlayerError::Task(e.to_string()))?;
+                Ok::<_, PlayerError>((path, fingerprint))
+            })
+        })
+        .collect();
+
+    for task in tasks {
+        let result = task
+            .await
+            .map_err(|e| PlayerError::Task(e.to_string()))
+            .and_then(|r| r);
+        match result {
+            Ok((path, Ok(fingerprint))) => {
+                cache.insert(&path, &fingerprint);
+                fingerprints.push((path, fingerprint));
+            }
+            Ok((path, Err(e))) => eprintln!("⚠️ Could not fingerprint {}: {}", path, e),
+            Err(e) => eprintln!("⚠️ Fingerprint task failed: {}", e),
+        }
+    }
+
+    if let Err(e) = cache.save() {
+        eprintln!("⚠️ Failed to save fingerprint cache: {}", e);
+    }
+
+    let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+    for (path, fingerprint) in fingerprints {
+        groups.entry(fingerprint).or_default().push(path);
+    }
+
+    let mut duplicates: Vec<Vec<String>> = groups
+        .into_values()
+        .filter(|g| g.len() > 1)
+        .map(|mut g| {
+            g.sort();
+            g
+        })
+        .collect();
+    duplicates.sort();
+
+    println!("Found {} duplicate groups across the library", duplicates.len());
+    Ok(duplicates)
 }
 
+// Bundle every saved playlist into a single zip archive for backup or migration
 #[tauri::command]
-pub fn load_playlist(app: AppHandle, name: String) -> Result<Playlist, String> {
-    let file_path = get_playlist_path(&app, &name)?;
+pub fn export_all_playlists(app: AppHandle, output_path: String) -> Result<(), PlayerError> {
+    use std::io::Write;
 
-    if !file_path.exists() {
-        return Err("Playlist not found".to_string());
+    println!("Exporting all playlists to {}", output_path);
+    let playlist_paths = playlist_files(&app)?;
+
+    let zip_error =
+        |e: zip::result::ZipError| PlayerError::Io(format!("Failed to write archive: {}", e));
+    let file = fs::File::create(&output_path)?;
+    let mut archive = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for path in &playlist_paths {
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let contents = fs::read(path)?;
+        archive.start_file(file_name, options).map_err(zip_error)?;
+        archive.write_all(&contents)?;
     }
+    archive.finish().map_err(zip_error)?;
 
-    let json = fs::read_to_string(file_path).map_err(|e| e.to_string())?;
-    let playlist: Playlist = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    println!("Exported {} playlists", playlist_paths.len());
+    Ok(())
+}
 
-    Ok(playlist)
+// Restore playlists from an archive made by export_all_playlists. Existing playlists are
+// skipped unless `overwrite` is set. Returns how many playlists were imported.
+#[tauri::command]
+pub fn import_all_playlists(
+    app: AppHandle,
+    archive_path: String,
+    overwrite: bool,
+) -> Result<usize, PlayerError> {
+    use std::io::Read;
+
+    // Playlists are small JSON files; anything bigger than this is not one of ours
+    const MAX_ENTRY_BYTES: u64 = 256 * 1024 * 1024;
+
+    println!("Importing playlists from {} (overwrite: {})", archive_path, overwrite);
+    let zip_error =
+        |e: zip::result::ZipError| PlayerError::Parse(format!("Failed to read archive: {}", e));
+    let file = fs::File::open(&archive_path)?;
+    let mut archive = zip::ZipArchive::new(file).map_err(zip_error)?;
+
+    let mut imported = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(zip_error)?;
+        if entry.is_dir() {
+            continue;
+        }
+
+        // Only top-level .json files; enclosed_name also rejects `..` and absolute paths
+        let Some(entry_path) = entry.enclosed_name() else {
+            eprintln!("⚠️ Skipping unsafe archive entry: {}", entry.name());
+            continue;
+        };
+        let is_json = entry_path.extension().is_some_and(|ext| ext == "json");
+        let name = match entry_path.file_stem().and_then(|s| s.to_str()) {
+            Some(name) if is_json && entry_path.components().count() == 1 => name.to_string(),
+            _ => {
+                eprintln!("⚠️ Skipping non-playlist archive entry: {}", entry.name());
+                continue;
+            }
+        };
+        if entry.size() > MAX_ENTRY_BYTES {
+            eprintln!("⚠️ Skipping oversized archive entry: {}", entry.name());
+            continue;
+        }
+
+        let mut json = String::new();
+        if let Err(e) = entry.read_to_string(&mut json) {
+            eprintln!("⚠️ Skipping unreadable archive entry {}: {}", name, e);
+            continue;
+        }
+        let mut playlist: Playlist = match serde_json::from_str(&json) {
+            Ok(playlist) => playlist,
+            Err(e) => {
+                eprintln!("⚠️ Skipping invalid playlist {}: {}", name, e);
+                continue;
+            }
+        };
+
+        let file_path = match get_playlist_path(&app, &name) {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!("⚠️ Skipping playlist with invalid name {}: {}", name, e);
+                continue;
+            }
+        };
+        if file_path.exists() && !overwrite {
+            println!("Skipping existing playlist: {}", name);
+            continue;
+        }
+
+        // The file name is what identifies a playlist, so keep the inner name in sync
+        playlist.name = name;
+        save_playlist_to_disk(&file_path, &playlist)?;
+        imported += 1;
+    }
+
+    println!("Imported {} playlists", imported);
+    Ok(imported)
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub struct PlaylistSummary {
-    pub name: String,
-    pub track_count: usize,
-    pub cover_image: Option<String>,
-    #[serde(default)]
-    pub tags: Vec<String>,
-    #[serde(default)]
-    pub folder_path: Option<String>,
+// Lowercase and trim so "Song " and "song" compare equal
+fn normalize_for_compare(s: &str) -> String {
+    s.trim().to_lowercase()
 }
 
 #[tauri::command]
-pub fn get_playlists(app: AppHandle) -> Result<Vec<PlaylistSummary>, String> {
-    let dir = get_playlist_dir(&app)?;
-    let mut playlists = Vec::new();
-
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if let Some(extension) = path.extension() {
-                    if extension == "json" {
-                        // Read the file to get the metadata
-                        if let Ok(json) = fs::read_to_string(&path) {
-                            if let Ok(playlist) = serde_json::from_str::<Playlist>(&json) {
-                                playlists.push(PlaylistSummary {
-                                    name: playlist.name,
-                                    track_count: playlist.tracks.len(),
-                                    cover_image: playlist.cover_image,
-                                    tags: playlist.tags,
-                                    folder_path: playlist.folder_path,
-                                });
-                            }
-                        }
+pub fn find_duplicate_tracks(
+    app: AppHandle,
+    name: String,
+) -> Result<Vec<Vec<usize>>, PlayerError> {
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    let playlist = load_playlist_from_disk(&file_path)?;
+    let track_count = playlist.tracks.len();
+
+    // Union-find over track indices so a path match and an artist+title match chain together
+    let mut parent: Vec<usize> = (0..track_count).collect();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        parent[i] = root;
+        root
+    }
+
+    let mut by_path: HashMap<&str, usize> = HashMap::new();
+    let mut by_artist_title: HashMap<(String, String), usize> = HashMap::new();
+
+    for (i, track) in playlist.tracks.iter().enumerate() {
+        let mut matches = Vec::new();
+
+        match by_path.get(track.path.as_str()) {
+            Some(&first) => matches.push(first),
+            None => {
+                by_path.insert(track.path.as_str(), i);
+            }
+        }
+
+        // Only compare by tags when both are present, otherwise untagged files would all match
+        if let (Some(artist), Some(title)) = (&track.artist, &track.title) {
+            let key = (normalize_for_compare(artist), normalize_for_compare(title));
+            if !key.0.is_empty() && !key.1.is_empty() {
+                match by_artist_title.get(&key) {
+                    Some(&first) => matches.push(first),
+                    None => {
+                        by_artist_title.insert(key, i);
                     }
                 }
             }
         }
+
+        for first in matches {
+            let a = find(&mut parent, first);
+            let b = find(&mut parent, i);
+            if a != b {
+                parent[b] = a;
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..track_count {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().push(i);
     }
 
-    Ok(playlists)
+    let mut duplicates: Vec<Vec<usize>> = groups.into_values().filter(|g| g.len() > 1).collect();
+    duplicates.sort_by_key(|g| g[0]);
+
+    println!("Found {} duplicate groups in playlist: {}", duplicates.len(), name);
+    Ok(duplicates)
 }
 
 #[tauri::command]
-pub fn delete_playlist(app: AppHandle, name: String) -> Result<(), String> {
-    let file_path = get_playlist_path(&app, &name)?;
+pub fn merge_playlists(
+    app: AppHandle,
+    source_names: Vec<String>,
+    target_name: String,
+    dedupe: bool,
+) -> Result<Playlist, PlayerError> {
+    println!(
+        "Merging playlists {:?} into {} (dedupe: {})",
+        source_names, target_name, dedupe
+    );
+    if source_names.is_empty() {
+        return Err(PlayerError::InvalidInput(
+            "No source playlists to merge".to_string(),
+        ));
+    }
 
-    if file_path.exists() {
-        fs::remove_file(file_path).map_err(|e| e.to_string())?;
+    let target_path = get_playlist_path(&app, &target_name)?;
+
+    // Load every source up front so a missing one doesn't leave a half-merged target
+    let mut sources = Vec::with_capacity(source_names.len());
+    for source_name in &source_names {
+        let source_path = get_playlist_path(&app, source_name)?;
+        if !source_path.exists() {
+            return Err(PlayerError::PlaylistNotFound(source_name.clone()));
+        }
+        sources.push(load_playlist_from_disk(&source_path)?);
     }
 
-    Ok(())
+    // Merging into an existing playlist appends to it and keeps its cover
+    let mut merged = if target_path.exists() {
+        load_playlist_from_disk(&target_path)?
+    } else {
+        Playlist {
+            name: target_name.clone(),
+            tracks: Vec::new(),
+            cover_image: sources[0].cover_image.clone(),
+            tags: Vec::new(),
+            folder_path: None,
+            folder: sources[0].folder.clone(),
+            sort_mode: SortMode::Manual,
+        }
+    };
+
+    for source in sources {
+        if source.name == merged.name {
+            continue;
+        }
+        merged.tracks.extend(source.tracks);
+        merged.tags.extend(source.tags);
+    }
+    merged.tags.sort();
+    merged.tags.dedup();
+
+    if dedupe {
+        let mut seen = HashSet::new();
+        merged.tracks.retain(|t| seen.insert(t.path.clone()));
+    }
+
+    save_playlist_to_disk(&target_path, &merged)?;
+    Ok(merged)
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct PlaylistDiff {
+    pub only_in_a: Vec<Track>,
+    pub only_in_b: Vec<Track>,
+    pub in_both: Vec<Track>,
+}
+
+// Normalize a track path for comparison: one separator style, no trailing separator,
+// and case-insensitive where the filesystem is
+fn normalize_path_for_compare(path: &str) -> String {
+    let normalized = path.trim().replace('\\', "/");
+    let normalized = normalized.trim_end_matches('/');
+    if cfg!(windows) {
+        normalized.to_lowercase()
+    } else {
+        normalized.to_string()
+    }
 }
 
 #[tauri::command]
-pub fn rename_playlist(app: AppHandle, old_name: String, new_name: String) -> Result<(), String> {
-    println!("Renaming playlist from {} to {}", old_name, new_name);
-    let old_path = get_playlist_path(&app, &old_name)?;
-    let new_path = get_playlist_path(&app, &new_name)?;
+pub fn diff_playlists(
+    app: AppHandle,
+    name_a: String,
+    name_b: String,
+) -> Result<PlaylistDiff, PlayerError> {
+    let path_a = get_playlist_path(&app, &name_a)?;
+    if !path_a.exists() {
+        return Err(PlayerError::PlaylistNotFound(name_a));
+    }
+    let path_b = get_playlist_path(&app, &name_b)?;
+    if !path_b.exists() {
+        return Err(PlayerError::PlaylistNotFound(name_b));
+    }
 
-    if !old_path.exists() {
-        return Err("Playlist not found".to_string());
+    let playlist_a = load_playlist_from_disk(&path_a)?;
+    let playlist_b = load_playlist_from_disk(&path_b)?;
+
+    let paths_a: HashSet<String> = playlist_a
+        .tracks
+        .iter()
+        .map(|t| normalize_path_for_compare(&t.path))
+        .collect();
+    let paths_b: HashSet<String> = playlist_b
+        .tracks
+        .iter()
+        .map(|t| normalize_path_for_compare(&t.path))
+        .collect();
+
+    // Tracks keep playlist A's order; shared tracks are reported once, as they appear in A
+    let mut diff = PlaylistDiff {
+        only_in_a: Vec::new(),
+        only_in_b: Vec::new(),
+        in_both: Vec::new(),
+    };
+    for track in playlist_a.tracks {
+        if paths_b.contains(&normalize_path_for_compare(&track.path)) {
+            diff.in_both.push(track);
+        } else {
+            diff.only_in_a.push(track);
+        }
     }
-    if new_path.exists() {
-        return Err("Playlist with new name already exists".to_string());
+    diff.only_in_b = playlist_b
+        .tracks
+        .into_iter()
+        .filter(|t| !paths_a.contains(&normalize_path_for_compare(&t.path)))
+        .collect();
+
+    println!(
+        "Diffed playlists {} and {}: {} only in A, {} only in B, {} in both",
+        name_a,
+        name_b,
+        diff.only_in_a.len(),
+        diff.only_in_b.len(),
+        diff.in_both.len()
+    );
+    Ok(diff)
+}
+
+#[tauri::command]
+pub fn export_playlist_m3u(
+    app: AppHandle,
+    name: String,
+    output_path: String,
+) -> Result<(), PlayerError> {
+    println!("Exporting playlist {} to M3U: {}", name, output_path);
+    let file_path = get_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
     }
 
-    // Read, update name field, write to new path, delete old path
-    let json = fs::read_to_string(&old_path).map_err(|e| e.to_string())?;
-    let mut playlist: Playlist = serde_json::from_str(&json).map_err(|e| e.to_string())?;
-    playlist.name = new_name;
+    let playlist = load_playlist_from_disk(&file_path)?;
 
-    save_playlist_to_disk(&new_path, &playlist)?;
-    fs::remove_file(&old_path).map_err(|e| e.to_string())?;
+    let mut m3u = String::from("#EXTM3U\n");
+    for track in &playlist.tracks {
+        m3u.push_str(&m3u_entry(track, &track.path));
+    }
 
+    fs::write(&output_path, m3u)?;
     Ok(())
 }
 
+// One #EXTINF line plus the location it describes
+fn m3u_entry(track: &Track, location: &str) -> String {
+    let display_name = match (&track.artist, &track.title) {
+        (Some(artist), Some(title)) => format!("{} - {}", artist, title),
+        (None, Some(title)) => title.clone(),
+        _ => track.filename.clone(),
+    };
+    format!(
+        "#EXTINF:{},{}\n{}\n",
+        track.duration.round() as i64,
+        display_name,
+        location
+    )
+}
+
+// Outcome of export_playlist_bundle: how many files were copied, plus (path, error)
+// for every track that couldn't be
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct BundleReport {
+    pub copied: usize,
+    pub failed: Vec<(String, String)>,
+}
+
+// Copy every track of a playlist into `destination` alongside an M3U that refers to the
+// copies by relative path, so the folder plays anywhere (e.g. from a USB stick)
 #[tauri::command]
-pub fn update_playlist_tags(app: AppHandle, name: String, tags: Vec<String>) -> Result<(), String> {
-    println!("Updating tags for playlist: {} to {:?}", name, tags);
+pub async fn export_playlist_bundle(
+    app: AppHandle,
+    name: String,
+    destination: String,
+) -> Result<BundleReport, PlayerError> {
+    println!("Exporting playlist {} as a bundle to {}", name, destination);
     let file_path = get_playlist_path(&app, &name)?;
 
     if !file_path.exists() {
-        return Err("Playlist not found".to_string());
+        return Err(PlayerError::PlaylistNotFound(name));
     }
 
-    let json = fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
-    let mut playlist: Playlist = serde_json::from_str(&json).map_err(|e| e.to_string())?;
-    playlist.tags = tags;
+    let playlist = load_playlist_from_disk(&file_path)?;
+    let bundle_dir = PathBuf::from(&destination);
+    if bundle_dir.exists() && !bundle_dir.is_dir() {
+        return Err(PlayerError::InvalidInput(format!(
+            "Destination is not a directory: {}",
+            destination
+        )));
+    }
+
+    let result = tauri::async_runtime::spawn(async move {
+        fs::create_dir_all(&bundle_dir)?;
+
+        // A track listed twice is only copied once
+        let mut copies: HashMap<String, String> = HashMap::new();
+        let mut failed = Vec::new();
+        let mut m3u = String::from("#EXTM3U\n");
+
+        for track in &playlist.tracks {
+            if let Some(copied_name) = copies.get(&track.path) {
+                m3u.push_str(&m3u_entry(track, copied_name));
+                continue;
+            }
+
+            let source = Path::new(&track.path);
+            let Some(file_name) = source.file_name() else {
+                failed.push((track.path.clone(), "Path has no file name".to_string()));
+                continue;
+            };
+            // Different folders often hold files with the same name ("01 Intro.mp3")
+            let target = unique_destination(bundle_dir.join(file_name));
+            match fs::copy(source, &target) {
+                Ok(_) => {
+                    let copied_name = target
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    m3u.push_str(&m3u_entry(track, &copied_name));
+                    copies.insert(track.path.clone(), copied_name);
+                }
+                Err(e) => {
+                    eprintln!("Bundle: failed to copy {}: {}", track.path, e);
+                    failed.push((track.path.clone(), e.to_string()));
+                }
+            }
+        }
+
+        // .m3u8 tells players the list is UTF-8
+        fs::write(bundle_dir.join(format!("{}.m3u8", playlist.name)), m3u)?;
+        Ok::<_, PlayerError>(BundleReport {
+            copied: copies.len(),
+            failed,
+        })
+    })
+    .await;
+
+    match result {
+        Ok(Ok(report)) => {
+            println!(
+                "Bundled playlist {}: {} files copied, {} failed",
+                name,
+                report.copied,
+                report.failed.len()
+            );
+            Ok(report)
+        }
+        Ok(Err(e)) => Err(e),
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
+
+// Outcome of import_playlist_m3u: the new playlist, plus how many entries pointed at
+// files that don't exist
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct M3uImportReport {
+    pub playlist: Playlist,
+    pub skipped: usize,
+}
+
+#[tauri::command]
+pub async fn import_playlist_m3u(
+    app: AppHandle,
+    m3u_path: String,
+    playlist_name: String,
+) -> Result<M3uImportReport, PlayerError> {
+    println!("Importing M3U {} as playlist {}", m3u_path, playlist_name);
+    let m3u_file = Path::new(&m3u_path);
+    let file_path = get_playlist_path(&app, &playlist_name)?;
+
+    if file_path.exists() {
+        return Err(PlayerError::AlreadyExists(format!(
+            "Playlist with this name already exists: {}",
+            playlist_name
+        )));
+    }
+
+    // Plain .m3u files are often not UTF-8, so decode lossily rather than failing
+    let bytes = fs::read(m3u_file)?;
+    let contents = String::from_utf8_lossy(&bytes);
+    let base_dir = m3u_file.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut track_paths = Vec::new();
+    let mut skipped = 0;
+
+    for line in contents.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // Resolve relative entries against the M3U file's own directory
+        let entry = Path::new(line);
+        let resolved = if entry.is_absolute() {
+            entry.to_path_buf()
+        } else {
+            base_dir.join(entry)
+        };
+
+        if resolved.is_file() {
+            track_paths.push(resolved.to_string_lossy().to_string());
+        } else {
+            eprintln!("M3U import: skipping missing file {:?}", resolved);
+            skipped += 1;
+        }
+    }
+
+    if track_paths.is_empty() {
+        return Err(PlayerError::InvalidInput(format!(
+            "No playable tracks found in M3U ({} missing entries skipped)",
+            skipped
+        )));
+    }
+
+    let tracks =
+        get_audio_metadata_batch(app.clone(), track_paths, false, None, false, None, None).await?;
+
+    let playlist = Playlist {
+        name: playlist_name,
+        tracks,
+        cover_image: None,
+        tags: Vec::new(),
+        folder_path: None,
+        folder: None,
+        sort_mode: SortMode::Manual,
+    };
 
     save_playlist_to_disk(&file_path, &playlist)?;
-    Ok(())
+    println!(
+        "Imported {} tracks from M3U ({} missing entries skipped)",
+        playlist.tracks.len(),
+        skipped
+    );
+
+    Ok(M3uImportReport { playlist, skipped })
 }
 
 #[tauri::command]
 pub async fn import_folder_as_playlist(
     app: AppHandle,
     folder_path: String,
-) -> Result<PlaylistSummary, String> {
+) -> Result<PlaylistSummary, PlayerError> {
     let folder = Path::new(&folder_path);
     if !folder.is_dir() {
-        return Err("Path is not a directory".to_string());
+        return Err(PlayerError::InvalidInput(format!(
+            "Path is not a directory: {}",
+            folder_path
+        )));
     }
 
     let folder_name = folder.file_name()
@@ -220,7 +1931,10 @@ pub async fn import_folder_as_playlist(
     }
 
     if audio_files.is_empty() {
-        return Err("No audio files found in folder".to_string());
+        return Err(PlayerError::InvalidInput(format!(
+            "No audio files found in folder: {}",
+            folder_path
+        )));
     }
 
     // Sort audio files by name
@@ -234,9 +1948,10 @@ pub async fn import_folder_as_playlist(
 
     for path in audio_files {
         let sem: Arc<Semaphore> = Arc::clone(&semaphore);
+        let app = app.clone();
         tasks.push(tokio::spawn(async move {
             let _permit = sem.acquire().await.unwrap();
-            get_audio_metadata(path, false).await
+            get_audio_metadata(app, path, false, None, false, None).await
         }));
     }
 
@@ -256,6 +1971,8 @@ pub async fn import_folder_as_playlist(
         cover_image: cover_image_path.clone(),
         tags: Vec::new(),
         folder_path: Some(folder_path.clone()),
+        folder: None,
+        sort_mode: SortMode::Manual,
     };
 
     // Auto-generate tags from unique artists found in the tracks
@@ -280,8 +1997,132 @@ pub async fn import_folder_as_playlist(
     Ok(PlaylistSummary {
         name: folder_name,
         track_count: playlist.tracks.len(),
+        total_duration: total_duration(&playlist.tracks),
+        total_size_bytes: total_size_bytes(&playlist.tracks),
         cover_image: cover_image_path,
         tags: playlist.tags,
         folder_path: playlist.folder_path,
+        folder: playlist.folder,
+        corrupt: false,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media_player::fallback_track;
+
+    // A fresh directory in the temp dir, unique per test so they can run in parallel
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pancake-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn playlist(name: &str, paths: &[&str]) -> Playlist {
+        Playlist {
+            name: name.to_string(),
+            tracks: paths
+                .iter()
+                .map(|p| fallback_track(p.to_string()))
+                .collect(),
+            cover_image: None,
+            tags: Vec::new(),
+            folder_path: None,
+            folder: None,
+            sort_mode: SortMode::Manual,
+        }
+    }
+
+    #[test]
+    fn rename_to_same_name_is_a_no_op() {
+        let dir = temp_dir("rename-same");
+        let path = dir.join("Chill.json");
+        save_playlist_to_disk(&path, &playlist("Chill", &["/music/a.mp3", "/music/b.mp3"]))
+            .unwrap();
+
+        rename_playlist_file(&path, &path, "Chill".to_string(), "Chill".to_string()).unwrap();
+
+        let loaded = load_playlist_from_disk(&path).unwrap();
+        assert_eq!(loaded.name, "Chill");
+        assert_eq!(loaded.tracks.len(), 2);
+    }
+
+    #[test]
+    fn rename_moves_the_playlist() {
+        let dir = temp_dir("rename-move");
+        let old_path = dir.join("Chill.json");
+        let new_path = dir.join("Relax.json");
+        save_playlist_to_disk(&old_path, &playlist("Chill", &["/music/a.mp3"])).unwrap();
+
+        rename_playlist_file(
+            &old_path,
+            &new_path,
+            "Chill".to_string(),
+            "Relax".to_string(),
+        )
+        .unwrap();
+
+        assert!(!old_path.exists());
+        let loaded = load_playlist_from_disk(&new_path).unwrap();
+        assert_eq!(loaded.name, "Relax");
+        assert_eq!(loaded.tracks.len(), 1);
+    }
+
+    #[test]
+    fn covers_dir_references_round_trip() {
+        let dir = temp_dir("cover-reference");
+        let cover = write_cover_file(&dir, b"not really a jpeg", "jpg").unwrap();
+        let path = dir.join("Covered.json");
+        let mut covered = playlist("Covered", &["/music/a.mp3"]);
+        covered.cover_image = Some(cover.to_string_lossy().to_string());
+        save_playlist_to_disk(&path, &covered).unwrap();
+
+        // Stored relative to the playlists dir...
+        let stored: Playlist = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let reference = stored.cover_image.unwrap();
+        assert!(reference.starts_with("covers/"), "{}", reference);
+
+        // ...and loaded back as a full path to the file
+        let loaded = load_playlist_from_disk(&path).unwrap();
+        let loaded_cover = PathBuf::from(loaded.cover_image.unwrap());
+        assert!(loaded_cover.is_absolute());
+        assert!(loaded_cover.exists());
+        assert_eq!(loaded_cover, cover);
+    }
+
+    #[test]
+    fn stream_urls_are_not_stored_paths() {
+        let url = "https://radio.example.com/live/stream.mp3";
+        assert_eq!(from_stored_path(url), url);
+        assert_eq!(to_stored_path(url), url);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn loads_windows_paths_on_unix() {
+        let dir = temp_dir("windows-paths");
+        let path = dir.join("Synced.json");
+        let mut synced = playlist("Synced", &["C:\\Music\\Artist\\song.mp3"]);
+        synced.folder_path = Some("C:\\Music\\Artist".to_string());
+        // Written as-is, like a playlist saved on Windows before paths were normalized
+        fs::write(&path, serde_json::to_string(&synced).unwrap()).unwrap();
+
+        let loaded = load_playlist_from_disk(&path).unwrap();
+        assert_eq!(loaded.tracks[0].path, "C:/Music/Artist/song.mp3");
+        assert_eq!(loaded.folder_path.as_deref(), Some("C:/Music/Artist"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_backslashes_survive_a_round_trip() {
+        let dir = temp_dir("unix-backslash");
+        let path = dir.join("Odd.json");
+        save_playlist_to_disk(&path, &playlist("Odd", &["/music/AC\\DC/song.mp3"])).unwrap();
+
+        let loaded = load_playlist_from_disk(&path).unwrap();
+        assert_eq!(loaded.tracks[0].path, "/music/AC\\DC/song.mp3");
+    }
+}
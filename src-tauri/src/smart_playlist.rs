@@ -0,0 +1,204 @@
+// src-tauri/src/smart_playlist.rs
+use crate::error::PlayerError;
+use crate::media_player::{collect_audio_files, get_audio_metadata_batch, Track};
+use crate::playlist::{validate_playlist_name, write_atomically};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tauri::Manager;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleOperator {
+    Equals,
+    NotEquals,
+    Contains,
+    NotContains,
+    GreaterThan,
+    LessThan,
+}
+
+// One criterion, e.g. `genre equals "Jazz"` or `year greater_than "2000"`
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct Rule {
+    pub field: String,
+    pub operator: RuleOperator,
+    pub value: String,
+}
+
+// A playlist defined by rules instead of a fixed track list; tracks are found by
+// scanning the search roots each time it's evaluated
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct SmartPlaylist {
+    pub name: String,
+    pub rules: Vec<Rule>,
+    pub search_roots: Vec<String>,
+}
+
+const TEXT_FIELDS: [&str; 8] = [
+    "artist",
+    "title",
+    "album",
+    "album_artist",
+    "genre",
+    "composer",
+    "filename",
+    "path",
+];
+const NUMERIC_FIELDS: [&str; 5] = ["year", "track_number", "disc_number", "duration", "rating"];
+
+fn text_field<'a>(track: &'a Track, field: &str) -> Option<&'a str> {
+    match field {
+        "artist" => track.artist.as_deref(),
+        "title" => track.title.as_deref(),
+        "album" => track.album.as_deref(),
+        "album_artist" => track.album_artist.as_deref(),
+        "genre" => track.genre.as_deref(),
+        "composer" => track.composer.as_deref(),
+        "filename" => Some(&track.filename),
+        "path" => Some(&track.path),
+        _ => None,
+    }
+}
+
+fn numeric_field(track: &Track, field: &str) -> Option<f64> {
+    match field {
+        "year" => track.year.map(f64::from),
+        "track_number" => track.track_number.map(f64::from),
+        "disc_number" => track.disc_number.map(f64::from),
+        "duration" => Some(track.duration).filter(|d| *d > 0.0),
+        "rating" => track.rating.map(f64::from),
+        _ => None,
+    }
+}
+
+// Reject unknown fields and rules that can't be evaluated before scanning anything
+fn validate_rules(rules: &[Rule]) -> Result<(), PlayerError> {
+    for rule in rules {
+        let field = rule.field.as_str();
+        if NUMERIC_FIELDS.contains(&field) {
+            if matches!(rule.operator, RuleOperator::Contains | RuleOperator::NotContains) {
+                return Err(PlayerError::InvalidInput(format!(
+                    "Operator {:?} can't be used with numeric field '{}'",
+                    rule.operator, field
+                )));
+            }
+            if rule.value.trim().parse::<f64>().is_err() {
+                return Err(PlayerError::InvalidInput(format!(
+                    "Rule on '{}' needs a numeric value, got '{}'",
+                    field, rule.value
+                )));
+            }
+        } else if TEXT_FIELDS.contains(&field) {
+            if matches!(rule.operator, RuleOperator::GreaterThan | RuleOperator::LessThan) {
+                return Err(PlayerError::InvalidInput(format!(
+                    "Operator {:?} can't be used with text field '{}'",
+                    rule.operator, field
+                )));
+            }
+        } else {
+            return Err(PlayerError::InvalidInput(format!("Unknown rule field: {}", field)));
+        }
+    }
+    Ok(())
+}
+
+// Text comparisons ignore case; a missing value only satisfies the negated operators
+fn matches_rule(track: &Track, rule: &Rule) -> bool {
+    if NUMERIC_FIELDS.contains(&rule.field.as_str()) {
+        let Ok(expected) = rule.value.trim().parse::<f64>() else {
+            return false;
+        };
+        let Some(actual) = numeric_field(track, &rule.field) else {
+            return rule.operator == RuleOperator::NotEquals;
+        };
+        return match rule.operator {
+            RuleOperator::Equals => actual == expected,
+            RuleOperator::NotEquals => actual != expected,
+            RuleOperator::GreaterThan => actual > expected,
+            RuleOperator::LessThan => actual < expected,
+            RuleOperator::Contains | RuleOperator::NotContains => false,
+        };
+    }
+
+    let actual = text_field(track, &rule.field)
+        .map(|s| s.trim().to_lowercase())
+        .unwrap_or_default();
+    let expected = rule.value.trim().to_lowercase();
+    match rule.operator {
+        RuleOperator::Equals => actual == expected,
+        RuleOperator::NotEquals => actual != expected,
+        RuleOperator::Contains => actual.contains(&expected),
+        RuleOperator::NotContains => !actual.contains(&expected),
+        RuleOperator::GreaterThan | RuleOperator::LessThan => false,
+    }
+}
+
+// Scan the search roots and return every track that matches all the rules
+#[tauri::command]
+pub async fn evaluate_smart_playlist(
+    app: AppHandle,
+    rules: Vec<Rule>,
+    search_roots: Vec<String>,
+) -> Result<Vec<Track>, PlayerError> {
+    validate_rules(&rules)?;
+
+    let mut files = Vec::new();
+    for root in &search_roots {
+        let root_path = Path::new(root);
+        if !root_path.is_dir() {
+            return Err(PlayerError::InvalidInput(format!(
+                "Path is not a directory: {}",
+                root
+            )));
+        }
+        collect_audio_files(root_path, true, &mut files);
+    }
+    // Overlapping roots would list the same file twice
+    files.sort();
+    files.dedup();
+
+    let tracks = get_audio_metadata_batch(app, files, false, None, false, None, None).await?;
+    let matched: Vec<Track> = tracks
+        .into_iter()
+        .filter(|track| rules.iter().all(|rule| matches_rule(track, rule)))
+        .collect();
+
+    eprintln!(
+        "Smart playlist: {} tracks match {} rules",
+        matched.len(),
+        rules.len()
+    );
+    Ok(matched)
+}
+
+fn get_smart_playlist_path(app: &AppHandle, name: &str) -> Result<PathBuf, PlayerError> {
+    validate_playlist_name(name)?;
+    let dir = app.path().app_data_dir()?.join("smart_playlists");
+
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+
+    Ok(dir.join(format!("{}.json", name)))
+}
+
+#[tauri::command]
+pub fn save_smart_playlist(app: AppHandle, playlist: SmartPlaylist) -> Result<(), PlayerError> {
+    validate_rules(&playlist.rules)?;
+    let file_path = get_smart_playlist_path(&app, &playlist.name)?;
+    let json = serde_json::to_string_pretty(&playlist)?;
+    write_atomically(&file_path, json.as_bytes())
+}
+
+#[tauri::command]
+pub fn load_smart_playlist(app: AppHandle, name: String) -> Result<SmartPlaylist, PlayerError> {
+    let file_path = get_smart_playlist_path(&app, &name)?;
+
+    if !file_path.exists() {
+        return Err(PlayerError::PlaylistNotFound(name));
+    }
+
+    let json = fs::read_to_string(file_path)?;
+    Ok(serde_json::from_str(&json)?)
+}
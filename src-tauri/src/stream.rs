@@ -0,0 +1,218 @@
+// src-tauri/src/stream.rs
+use crate::error::PlayerError;
+use crate::media_player::{fallback_track, Track};
+use std::io::Read;
+use std::time::Duration;
+use symphonia::core::io::{MediaSourceStream, ReadOnlySource};
+use symphonia::core::meta::StandardTagKey;
+use symphonia::core::probe::Hint;
+
+// Most stations send metadata every 8-32 KB; give up after a few empty blocks
+const MAX_ICY_BLOCKS: usize = 4;
+// Embedded tags sit at the start of a file, so never pull more than this over the network
+const MAX_PROBE_BYTES: u64 = 4 * 1024 * 1024;
+
+fn http_error(e: ureq::Error) -> PlayerError {
+    match e {
+        ureq::Error::Status(code, response) => PlayerError::Io(format!(
+            "Server returned HTTP {} {}",
+            code,
+            response.status_text()
+        )),
+        ureq::Error::Transport(transport) => {
+            PlayerError::Io(format!("Failed to connect: {}", transport))
+        }
+    }
+}
+
+// Symphonia hint from the Content-Type, falling back to the URL's extension
+fn stream_hint(content_type: &str, url: &str) -> Hint {
+    let mut hint = Hint::new();
+    let extension = match content_type {
+        "audio/mpeg" | "audio/mp3" => Some("mp3"),
+        "audio/aac" | "audio/aacp" => Some("aac"),
+        "audio/mp4" | "audio/x-m4a" => Some("m4a"),
+        "audio/ogg" | "application/ogg" => Some("ogg"),
+        "audio/flac" | "audio/x-flac" => Some("flac"),
+        "audio/wav" | "audio/x-wav" => Some("wav"),
+        _ => None,
+    };
+    let url_extension = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, ext)| ext.to_lowercase());
+
+    if let Some(ext) = extension.map(str::to_string).or(url_extension) {
+        hint.with_extension(&ext);
+    }
+    hint.mime_type(content_type);
+    hint
+}
+
+// Pull StreamTitle out of an ICY metadata block: StreamTitle='Artist - Title';StreamUrl='';
+fn parse_stream_title(block: &str) -> Option<String> {
+    let start = block.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &block[start..];
+    // Titles can contain apostrophes, so the value ends at the quote before the semicolon
+    let end = rest.find("';").unwrap_or(rest.len());
+    let title = rest[..end].trim();
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.to_string())
+    }
+}
+
+// Read ICY metadata blocks until one carries a StreamTitle. Each block follows `metaint`
+// bytes of audio and is prefixed by its length in 16-byte units.
+fn read_icy_title(reader: &mut impl Read, metaint: usize) -> Result<Option<String>, PlayerError> {
+    let mut audio = vec![0u8; metaint];
+    for _ in 0..MAX_ICY_BLOCKS {
+        reader.read_exact(&mut audio)?;
+        let mut length = [0u8; 1];
+        reader.read_exact(&mut length)?;
+        let mut block = vec![0u8; length[0] as usize * 16];
+        reader.read_exact(&mut block)?;
+
+        let text = String::from_utf8_lossy(&block);
+        if let Some(title) = parse_stream_title(text.trim_end_matches('\0')) {
+            return Ok(Some(title));
+        }
+    }
+    Ok(None)
+}
+
+// Probe the start of a remote file for embedded tags
+fn read_embedded_tags(
+    reader: Box<dyn Read + Send + Sync>,
+    hint: &Hint,
+    track: &mut Track,
+) -> Result<(), PlayerError> {
+    let source = ReadOnlySource::new(reader.take(MAX_PROBE_BYTES));
+    let mss = MediaSourceStream::new(Box::new(source), Default::default());
+
+    let mut probed = symphonia::default::get_probe()
+        .format(hint, mss, &Default::default(), &Default::default())
+        .map_err(|e| PlayerError::UnsupportedFormat(format!("Failed to probe stream: {}", e)))?;
+
+    if let Some(params) = probed.format.default_track().map(|t| &t.codec_params) {
+        if let (Some(n_frames), Some(sample_rate)) = (params.n_frames, params.sample_rate) {
+            if sample_rate > 0 {
+                track.duration = n_frames as f64 / sample_rate as f64;
+            }
+        }
+    }
+
+    // ID3v2 shows up in the probe metadata, Vorbis comments and MP4 atoms in the format's
+    let mut revisions = Vec::new();
+    if let Some(rev) = probed.metadata.get().and_then(|m| m.current().cloned()) {
+        revisions.push(rev);
+    }
+    if let Some(rev) = probed.format.metadata().current().cloned() {
+        revisions.push(rev);
+    }
+
+    for rev in &revisions {
+        for tag in rev.tags() {
+            let value = tag.value.to_string().trim().to_string();
+            if value.is_empty() {
+                continue;
+            }
+            match tag.std_key {
+                Some(StandardTagKey::Artist) if track.artist.is_none() => {
+                    track.artist = Some(value)
+                }
+                Some(StandardTagKey::TrackTitle) => track.title = Some(value),
+                Some(StandardTagKey::Album) if track.album.is_none() => track.album = Some(value),
+                Some(StandardTagKey::Genre) if track.genre.is_none() => track.genre = Some(value),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+// Metadata for an internet radio station or remote file. Icecast/Shoutcast streams report
+// the current song through ICY metadata; plain files are probed for embedded tags.
+#[tauri::command]
+pub async fn get_stream_metadata(url: String) -> Result<Track, PlayerError> {
+    let lower = url.to_lowercase();
+    if !lower.starts_with("http://") && !lower.starts_with("https://") {
+        return Err(PlayerError::InvalidInput(format!(
+            "Not an HTTP(S) URL: {}",
+            url
+        )));
+    }
+
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(10))
+            .timeout_read(Duration::from_secs(15))
+            .redirects(5)
+            .build();
+
+        let response = agent
+            .get(&url)
+            .set("Icy-MetaData", "1")
+            .call()
+            .map_err(http_error)?;
+
+        // Redirects may have taken us somewhere else; that URL names the file better
+        let final_url = response.get_url().to_string();
+        let content_type = response.content_type().to_lowercase();
+        let header = |name: &str| {
+            response
+                .header(name)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let station_name = header("icy-name");
+        let station_genre = header("icy-genre");
+        let metaint = header("icy-metaint").and_then(|v| v.parse::<usize>().ok());
+
+        let mut track = fallback_track(url.clone());
+        if let Some(name) = &station_name {
+            track.filename = name.clone();
+        }
+        track.genre = station_genre;
+
+        let hint = stream_hint(&content_type, &final_url);
+        let mut reader = response.into_reader();
+
+        match metaint.filter(|&n| n > 0) {
+            Some(metaint) => {
+                // Live streams have no length and no embedded tags worth waiting for
+                match read_icy_title(&mut reader, metaint)? {
+                    Some(stream_title) => match stream_title.split_once(" - ") {
+                        Some((artist, title)) => {
+                            track.artist = Some(artist.trim().to_string());
+                            track.title = Some(title.trim().to_string());
+                        }
+                        None => track.title = Some(stream_title),
+                    },
+                    None => track.title = station_name.or(track.title),
+                }
+            }
+            None => {
+                if let Err(e) = read_embedded_tags(reader, &hint, &mut track) {
+                    eprintln!("⚠️ No embedded tags in {}: {}", url, e);
+                }
+            }
+        }
+
+        track.artists = track.artist.clone().map(|artist| vec![artist]);
+        println!(
+            "Stream metadata for {} - Artist: {:?}, Title: {:?}",
+            url, track.artist, track.title
+        );
+        Ok(track)
+    })
+    .await;
+
+    match result {
+        Ok(inner_res) => inner_res,
+        Err(e) => Err(PlayerError::Task(e.to_string())),
+    }
+}
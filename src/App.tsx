@@ -26,6 +26,7 @@ import { useKeyboardShortcuts } from "./hooks/useKeyboardShortcuts";
 
 // Types
 import { Track, Tab, PlaylistSummary } from "./types";
+import { formatError } from "./utils";
 
 const FAVOURITE_TAGS_KEY = "pancake_favourite_tags";
 
@@ -55,7 +56,7 @@ const AppContent: React.FC = () => {
 
   const loadPlaylists = useCallback(async () => {
     try {
-      const loadedPlaylists = await invoke<PlaylistSummary[]>("get_playlists");
+      const loadedPlaylists = await invoke<PlaylistSummary[]>("get_playlists", { includeSizes: true });
       setPlaylists(loadedPlaylists);
     } catch (e) {
       console.error("Failed to load playlists:", e);
@@ -214,7 +215,7 @@ const AppContent: React.FC = () => {
       setIsSaveModalOpen(false);
     } catch (e) {
       console.error("Failed to save playlist:", e);
-      alert("Failed to save playlist: " + e);
+      alert("Failed to save playlist: " + formatError(e));
     }
   };
 
@@ -257,7 +258,7 @@ const AppContent: React.FC = () => {
       }, 50);
     } catch (e) {
       console.error("❌ Failed to load playlist:", e);
-      alert("Failed to load playlist: " + e);
+      alert("Failed to load playlist: " + formatError(e));
     }
   };
 
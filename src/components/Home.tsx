@@ -5,6 +5,7 @@ import { Plus, Loader2, RefreshCw, Music, FolderUp, ChevronDown } from "lucide-r
 import PlaylistCard from "./PlaylistCard";
 import TagContextMenu from "./TagContextMenu";
 import { PlaylistSummary } from "../types";
+import { formatError } from "../utils";
 
 interface HomeProps {
   playlists: PlaylistSummary[];
@@ -128,7 +129,7 @@ const Home: React.FC<HomeProps> = ({ playlists, favouriteTags, onToggleFavourite
       }
     } catch (e) {
       console.error("Failed to import folder:", e);
-      alert("Failed to import folder: " + e);
+      alert("Failed to import folder: " + formatError(e));
     } finally {
       setLoading(false);
     }
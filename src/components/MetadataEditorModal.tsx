@@ -2,6 +2,7 @@ import React, { useState, useEffect } from "react";
 import { X, Music, Clock } from "lucide-react";
 import { invoke } from "@tauri-apps/api/core";
 import { Track, AudioFileInfo } from "../types";
+import { formatError } from "../utils";
 
 interface MetadataEditorModalProps {
   isOpen: boolean;
@@ -63,7 +64,7 @@ const MetadataEditorModal: React.FC<MetadataEditorModalProps> = ({
       await onSave(artist.trim(), title.trim(), album.trim());
       onClose();
     } catch (err) {
-      setError(err instanceof Error ? err.message : "Failed to save metadata");
+      setError(err ? formatError(err) : "Failed to save metadata");
     } finally {
       setIsSaving(false);
     }
@@ -2,6 +2,7 @@ import React, { useEffect, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { ArrowLeft, Play, Clock, Music2, GripVertical, Pencil, Check, X, Plus, FolderUp, Trash2, FilePlus2, FolderOpen } from 'lucide-react';
 import { Track, Playlist } from '../types';
+import { formatError } from '../utils';
 import { open } from '@tauri-apps/plugin-dialog';
 import { convertFileSrc } from '@tauri-apps/api/core';
 import { openPath } from '@tauri-apps/plugin-opener';
@@ -154,7 +155,7 @@ const PlaylistDetailView: React.FC<PlaylistDetailViewProps> = ({ playlistName, o
     if (isAddingTag) {
         const fetchAllTags = async () => {
             try {
-                const playlistsResult = await invoke<any[]>('get_playlists');
+                const playlistsResult = await invoke<any[]>('get_playlists', { includeSizes: false });
                 const uniqueTags = Array.from(new Set(playlistsResult.flatMap(p => p.tags || []))).sort();
                 setAllLibraryTags(uniqueTags);
             } catch (e) {
@@ -287,7 +288,7 @@ const PlaylistDetailView: React.FC<PlaylistDetailViewProps> = ({ playlistName, o
       setIsEditingTitle(false);
     } catch (e) {
       console.error("Failed to rename playlist:", e);
-      alert("Failed to rename playlist: " + e);
+      alert("Failed to rename playlist: " + formatError(e));
     }
   };
 
@@ -385,7 +386,7 @@ const PlaylistDetailView: React.FC<PlaylistDetailViewProps> = ({ playlistName, o
       if (selected && Array.isArray(selected)) {
         setLoading(true);
         const newTrackResults = await Promise.all(
-          selected.map(path => invoke<any>('get_audio_metadata', { filePath: path, enableGapless: false }))
+          selected.map(path => invoke<any>('get_audio_metadata', { filePath: path, enableGapless: false, skipCover: false }))
         );
 
         const mappedNewTracks: Track[] = newTrackResults.map((t: any) => ({
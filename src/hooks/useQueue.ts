@@ -78,6 +78,7 @@ export const useQueue = ({ enableGapless }: UseQueueProps) => {
           const metadata = await invoke<RawMetadata>("get_audio_metadata", {
             filePath: track.path,
             enableGapless: enableGapless,
+            skipCover: false,
           });
           return { trackId: track.id, metadata, success: true };
         } catch (e) {
@@ -208,6 +209,7 @@ export const useQueue = ({ enableGapless }: UseQueueProps) => {
           artist: artist || null,
           title: title || null,
           album: album || null,
+          backup: false,
         });
 
         setQueue((prev) =>
@@ -18,6 +18,27 @@ export interface RawMetadata {
   title: string | null;
   album: string | null;
   cover_image?: string;
+  genre?: string | null;
+  year?: number | null;
+  track_number?: number | null;
+  album_artist?: string | null;
+  composer?: string | null;
+  replaygain_track_gain?: number | null;
+  replaygain_track_peak?: number | null;
+  disc_number?: number | null;
+  total_tracks?: number | null;
+  total_discs?: number | null;
+  rating?: number | null;
+  artists?: string[] | null;
+  comment?: string | null;
+  grouping?: string | null;
+  compilation?: boolean | null;
+  bpm?: number | null;
+  musical_key?: string | null;
+  sample_rate?: number | null;
+  bit_depth?: number | null;
+  start_offset?: number | null;
+  gapless_used?: boolean;
 }
 
 export interface Playlist {
@@ -26,14 +47,46 @@ export interface Playlist {
   cover_image?: string;
   tags?: string[];
   folder_path?: string;
+  folder?: string;
+  sort_mode?: SortMode;
 }
 
+export type SortMode = "manual" | "by_title" | "by_artist" | "by_album" | "by_duration";
+
 export interface PlaylistSummary {
   name: string;
   track_count: number;
+  total_duration?: number;
+  total_size_bytes?: number;
   cover_image?: string;
   tags?: string[];
   folder_path?: string;
+  folder?: string;
+  corrupt?: boolean;
+}
+
+export interface LibraryStats {
+  playlist_count: number;
+  unique_tracks: number;
+  total_duration: number;
+  formats: [string, number][];
+  top_artists: [string, number][];
+}
+
+export interface BundleReport {
+  copied: number;
+  failed: [string, string][];
+}
+
+export interface M3uImportReport {
+  playlist: Playlist;
+  skipped: number;
+}
+
+export interface PlaylistDiff {
+  only_in_a: Track[];
+  only_in_b: Track[];
+  in_both: Track[];
 }
 
 export interface AudioFileInfo {
@@ -47,13 +100,171 @@ export interface AudioFileInfo {
   sample_rate: number | null;
   channels: number | null;
   bit_depth: number | null;
+  sample_format: string | null;
+  encoder_delay?: number | null;
+  encoder_padding?: number | null;
   artist: string | null;
   title: string | null;
   album: string | null;
   cover_image?: string;
 }
 
+// Error shape returned by every backend command (see src-tauri/src/error.rs)
+export interface PlayerError {
+  kind:
+    | "FileNotFound"
+    | "PlaylistNotFound"
+    | "AlreadyExists"
+    | "InvalidInput"
+    | "UnsupportedFormat"
+    | "MetadataRead"
+    | "MetadataWrite"
+    | "Decode"
+    | "Io"
+    | "Parse"
+    | "Task";
+  message: string;
+}
+
 export type RepeatMode = "none" | "all" | "one";
+export interface PlaybackState {
+  playlist_name: string | null;
+  track_index: number;
+  position: number;
+  volume: number;
+  shuffle: boolean;
+  repeat: RepeatMode;
+  shuffle_seed?: string | null;
+}
+
+export interface ShuffleOrder {
+  order: number[];
+  seed: string;
+}
+
+export interface EncodingInfo {
+  encoder: string | null;
+  bitrate_mode: "CBR" | "VBR" | "ABR" | null;
+  lame_version: string | null;
+  lame_quality: string | null;
+}
+
+export interface RepairResult {
+  path: string;
+  fixes: string[];
+  error: string | null;
+}
+
+export interface CoverArt {
+  data: string;
+  mime_type: string;
+  width: number;
+  height: number;
+}
+
+export interface ScanProgress {
+  processed: number;
+  total: number;
+  current_path: string;
+}
+
+export interface Capabilities {
+  metadata_read: string[];
+  metadata_write: string[];
+  cover_art: string[];
+}
+
+export interface TranscodeProgress {
+  input_path: string;
+  progress: number;
+}
+
+export interface ScanReport {
+  tracks: Track[];
+  errors: [string, string][];
+}
+
+// decode_preview returns an ArrayBuffer: sample rate and channel count as little-endian
+// u32s, then interleaved 16-bit samples. This is its decoded form.
+export interface PcmPreview {
+  pcm: Int16Array;
+  sample_rate: number;
+  channels: number;
+}
+
+export interface PeakInfo {
+  true_peak_dbfs: number;
+  channel_peaks_dbfs: number[];
+  clipped_samples: number;
+}
+
+export interface TrackGain {
+  path: string;
+  gain_db: number;
+  peak: number;
+}
+
+export interface AlbumGain {
+  tracks: TrackGain[];
+  album_gain_db: number;
+  album_peak: number;
+}
+
+export interface CrossfadeInfo {
+  duration: number;
+  step: number;
+  tail_level_dbfs: number;
+  head_level_dbfs: number;
+  gain_a: number[];
+  gain_b: number[];
+}
+
+export interface TrackStatus {
+  index: number;
+  path: string;
+  exists: boolean;
+  playable: boolean;
+}
+
+export interface Chapter {
+  title: string;
+  start: number;
+  end: number;
+}
+
+export interface ImageInfo {
+  format: string;
+  mime_type: string;
+  width: number;
+  height: number;
+}
+
+export interface Marker {
+  name: string;
+  position_secs: number;
+  color: string | null;
+}
+
+export type RuleOperator =
+  | "equals"
+  | "not_equals"
+  | "contains"
+  | "not_contains"
+  | "greater_than"
+  | "less_than";
+
+export interface Rule {
+  field: string;
+  operator: RuleOperator;
+  value: string;
+}
+
+export interface SmartPlaylist {
+  name: string;
+  rules: Rule[];
+  search_roots: string[];
+}
+
 export type Tab = "queue" | "visualizer" | "home" | "playlist" | "legal" | "favourites";
 export type VisualizerStyle =
   | "mirror"